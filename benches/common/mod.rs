@@ -0,0 +1,31 @@
+//! Bits shared between the benches. Paths are relative to the crate root, which is where
+//! `cargo bench` runs them from.
+
+// Each bench pulls this in separately, and none of them use all of it
+#![allow(dead_code)]
+
+use spell_tower_solver::board::Board;
+use spell_tower_solver::dictionary::Dictionary;
+use spell_tower_solver::InputBoard;
+
+pub const DICT_PATH: &str = "nwl/nwl2023.txt";
+
+/// The full 13x9 sample board
+pub const FULL_BOARD: &str = "sample-input/board-1.ron";
+
+/// Just the bottom couple of rows, small enough to play all the way through
+pub const SMALL_BOARD: &str = "sample-input/small-board.ron";
+
+pub fn input_board(path: &str) -> InputBoard {
+    ron::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+pub fn board(path: &str) -> Board {
+    let input = input_board(path);
+    Board::new_from(input.board, input.mults, 3)
+}
+
+pub fn dictionary() -> Dictionary {
+    let words = std::fs::read_to_string(DICT_PATH).unwrap();
+    Dictionary::from_words(words.lines(), 3)
+}
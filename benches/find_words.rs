@@ -0,0 +1,50 @@
+mod common;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use spell_tower_solver::bitboard;
+use spell_tower_solver::board::Board;
+use spell_tower_solver::position::Position;
+
+/// Every neighbor of every position, worked out on the spot vs looked up in a table
+fn neighbors(c: &mut Criterion) {
+    let positions = (0..Board::HEIGHT)
+        .flat_map(|row| (0..Board::WIDTH).map(move |col| Position::new(row, col)))
+        .collect::<Vec<Position>>();
+
+    let mut group = c.benchmark_group("neighbors");
+    group.throughput(Throughput::Elements(positions.len() as u64));
+    group.bench_function("computed", |b| {
+        b.iter(|| {
+            positions
+                .iter()
+                .map(|p| p.neighbors(Board::WIDTH - 1, Board::HEIGHT - 1).len())
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("table", |b| {
+        b.iter(|| {
+            positions
+                .iter()
+                .map(|p| bitboard::NEIGHBORS[bitboard::index_of(p)].as_slice().len())
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+}
+
+/// How many words a second we can find on a full board
+fn find_words(c: &mut Criterion) {
+    let dict = common::dictionary();
+    let board = common::board(common::FULL_BOARD);
+    let found = board.find_words(&dict, usize::MAX).len();
+
+    let mut group = c.benchmark_group("find_words");
+    group.throughput(Throughput::Elements(found as u64));
+    group.bench_function("sample board", |b| {
+        b.iter(|| black_box(&board).find_words(&dict, usize::MAX))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, neighbors, find_words);
+criterion_main!(benches);
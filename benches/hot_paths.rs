@@ -0,0 +1,74 @@
+mod common;
+
+use clap::Parser;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use spell_tower_solver::dictionary::Dictionary;
+use spell_tower_solver::game;
+use spell_tower_solver::Args;
+
+fn args_for(cli: &str) -> Args {
+    Args::parse_from(format!("spell-tower-solver -q {}", cli).split_whitespace())
+}
+
+/// Reading and indexing the whole word list
+fn dictionary_load(c: &mut Criterion) {
+    let args = args_for(&format!("--dict-path {}", common::DICT_PATH));
+
+    let mut group = c.benchmark_group("dictionary");
+    group.sample_size(10);
+    group.bench_function("load", |b| b.iter(|| Dictionary::new(&args)));
+    group.finish();
+}
+
+/// Scoring, then playing, every word on the full sample board
+fn moves(c: &mut Criterion) {
+    let dict = common::dictionary();
+    let board = common::board(common::FULL_BOARD);
+    let words = board.find_words(&dict, usize::MAX);
+
+    let mut group = c.benchmark_group("moves");
+    group.throughput(Throughput::Elements(words.len() as u64));
+    group.bench_function("score_for", |b| {
+        b.iter(|| {
+            words
+                .iter()
+                .map(|w| black_box(&board).score_for(&w.word, &w.path))
+                .sum::<u32>()
+        })
+    });
+    group.bench_function("evolve_via", |b| {
+        b.iter_batched(
+            || words.clone(),
+            |words| {
+                words
+                    .into_iter()
+                    .map(|w| black_box(&board).evolve_via(w))
+                    .collect::<Vec<_>>()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+/// Whole games with the beam search, dictionary already loaded
+fn play_game(c: &mut Criterion) {
+    let dict = common::dictionary();
+
+    let mut group = c.benchmark_group("play_game");
+    group.sample_size(10);
+    for (name, path, cli) in [
+        ("small board", common::SMALL_BOARD, ""),
+        ("full board -c 3 -g 100", common::FULL_BOARD, "-c 3 -g 100"),
+    ] {
+        let args = args_for(cli);
+        let input = common::input_board(path);
+        group.bench_function(name, |b| {
+            b.iter(|| game::play_game(&args, &dict, input.board, input.mults.clone()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, dictionary_load, moves, play_game);
+criterion_main!(benches);
@@ -0,0 +1,151 @@
+//! Bit-level helpers for the board. Every cell gets an index, row by row (so indexes sort the
+//! same way `Position`s do), and a set of cells is a `CellMask` with one bit per index. A whole
+//! board is 117 cells, so a set of them fits in a single `u128`.
+
+use crate::position::Position;
+
+pub const WIDTH: usize = 9;
+pub const HEIGHT: usize = 13;
+pub const CELLS: usize = WIDTH * HEIGHT;
+
+pub type CellMask = u128;
+
+pub const fn index(row: usize, col: usize) -> usize {
+    row * WIDTH + col
+}
+
+pub fn index_of(pos: &Position) -> usize {
+    index(pos.row as usize, pos.col as usize)
+}
+
+pub fn position_of(idx: usize) -> Position {
+    Position::new(idx / WIDTH, idx % WIDTH)
+}
+
+pub const fn bit(idx: usize) -> CellMask {
+    1 << idx
+}
+
+/// Every cell in `row`
+pub const fn row_mask(row: usize) -> CellMask {
+    ((1 << WIDTH) - 1) << index(row, 0)
+}
+
+/// Every cell in `mask`, lowest index (top left) first
+pub fn cells(mut mask: CellMask) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let idx = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(idx)
+    })
+}
+
+/// Up to 8 neighboring cells, in a fixed order
+#[derive(Clone, Copy)]
+pub struct Neighbors {
+    cells: [u8; 8],
+    len: u8,
+}
+
+impl Neighbors {
+    pub fn as_slice(&self) -> &[u8] {
+        &self.cells[..self.len as usize]
+    }
+}
+
+/// For every cell: the cells a word can move to next. Same order as `Position::neighbors()`:
+/// north, east, west, south, then north west, north east, south west, south east.
+pub static NEIGHBORS: [Neighbors; CELLS] = neighbor_table(true);
+
+/// For every cell: the cells directly north, east, west and south of it
+pub static CARDINAL_NEIGHBORS: [CellMask; CELLS] = cardinal_masks();
+
+const fn neighbor_table(diagonals: bool) -> [Neighbors; CELLS] {
+    const STEPS: [(isize, isize); 8] = [
+        (-1, 0),
+        (0, 1),
+        (0, -1),
+        (1, 0),
+        (-1, -1),
+        (-1, 1),
+        (1, -1),
+        (1, 1),
+    ];
+    let steps = if diagonals { 8 } else { 4 };
+
+    let mut table = [Neighbors {
+        cells: [0; 8],
+        len: 0,
+    }; CELLS];
+    let mut idx = 0;
+    while idx < CELLS {
+        let row = (idx / WIDTH) as isize;
+        let col = (idx % WIDTH) as isize;
+        let mut s = 0;
+        while s < steps {
+            let (r, c) = (row + STEPS[s].0, col + STEPS[s].1);
+            if r >= 0 && r < HEIGHT as isize && c >= 0 && c < WIDTH as isize {
+                let n = &mut table[idx];
+                n.cells[n.len as usize] = index(r as usize, c as usize) as u8;
+                n.len += 1;
+            }
+            s += 1;
+        }
+        idx += 1;
+    }
+    table
+}
+
+const fn cardinal_masks() -> [CellMask; CELLS] {
+    let table = neighbor_table(false);
+    let mut masks = [0; CELLS];
+    let mut idx = 0;
+    while idx < CELLS {
+        let mut n = 0;
+        while n < table[idx].len as usize {
+            masks[idx] |= bit(table[idx].cells[n] as usize);
+            n += 1;
+        }
+        idx += 1;
+    }
+    masks
+}
+
+#[cfg(test)]
+mod bitboard_tests {
+    use super::*;
+
+    #[test]
+    /// The tables have to agree with `Position`, order and all
+    fn tables_match_positions() {
+        for idx in 0..CELLS {
+            let pos = position_of(idx);
+            assert_eq!(index_of(&pos), idx);
+
+            let expected = pos
+                .neighbors(WIDTH - 1, HEIGHT - 1)
+                .iter()
+                .map(|p| index_of(p) as u8)
+                .collect::<Vec<u8>>();
+            assert_eq!(NEIGHBORS[idx].as_slice(), expected.as_slice());
+
+            let cardinal = pos
+                .cardinal_neighbors(WIDTH - 1, HEIGHT - 1)
+                .iter()
+                .fold(0, |mask, p| mask | bit(index_of(p)));
+            assert_eq!(CARDINAL_NEIGHBORS[idx], cardinal);
+        }
+    }
+
+    #[test]
+    fn cells_come_out_in_order() {
+        let mask = bit(100) | bit(3) | row_mask(1);
+        assert_eq!(
+            cells(mask).collect::<Vec<usize>>(),
+            vec![3, 9, 10, 11, 12, 13, 14, 15, 16, 17, 100]
+        );
+    }
+}
@@ -1,82 +1,153 @@
+use crate::bitboard::{self, CellMask, CARDINAL_NEIGHBORS, NEIGHBORS};
 use crate::position::Position;
 
-use std::collections::hash_map::DefaultHasher;
-use std::collections::HashSet;
-use std::hash::Hasher;
-use std::{fmt, hash::Hash};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
 
 use crate::dictionary::Dictionary;
+use crate::scoring::Scoring;
+use crate::tile::Tile;
 
 use deepsize::DeepSizeOf;
 
-const LETTER_SCORES: &'static [u32] = &[
-    1,  // a
-    4,  // b
-    4,  // c
-    3,  // d
-    1,  // e
-    5,  // f
-    3,  // g
-    5,  // h
-    1,  // i
-    9,  // j
-    6,  // k
-    2,  // l
-    4,  // m
-    2,  // n
-    1,  // o
-    4,  // p
-    12, // q
-    2,  // r
-    1,  // s
-    2,  // t
-    1,  // u
-    5,  // v
-    5,  // w
-    9,  // x
-    5,  // y
-    11, // z
-];
-
 #[derive(Clone, Debug, Eq, Hash, PartialEq, DeepSizeOf)]
 pub struct FoundWord {
     pub path: Vec<Position>,
     pub word: String,
     pub score: u32,
+    /// How many paths spell `word` and blow up exactly the same tiles as `path` does. Only ever
+    /// more than 1 when searching with `WordSearch::distinct_effects`.
+    pub paths: u32,
+}
+
+/// Where the points for a move come from, see `Board::explain_score()`. Every destroyed letter
+/// is listed once, under the first of path, row clear or blast that accounts for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScoreBreakdown {
+    pub word: String,
+    /// The tiles the word is spelled with
+    pub path: Vec<(Position, Tile)>,
+    /// Tiles on a row cleared by a j, q, x or z on the path
+    pub row_cleared: Vec<(Position, Tile)>,
+    /// Tiles next to the path, blown up because the word is 5+ letters long
+    pub blasted: Vec<(Position, Tile)>,
+    /// Blocks that got blown up. They're worth nothing, but they're gone.
+    pub blocks: Vec<Position>,
+    pub letter_sum: u32,
+    pub length: u32,
+    /// How many multipliers the path goes over
+    pub multipliers: u32,
+    /// What the score gets multiplied by for those multipliers
+    pub multiplier: u32,
+    pub score: u32,
+}
+
+impl fmt::Display for ScoreBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let letters =
+            |tiles: &[(Position, Tile)]| tiles.iter().map(|(_, t)| t.symbol()).collect::<String>();
+
+        write!(f, "{}", self.word)?;
+        if self.multipliers > 0 {
+            let plural = if self.multipliers == 1 { "" } else { "s" };
+            write!(f, " ({} multiplier{})", self.multipliers, plural)?;
+        }
+        if !self.blocks.is_empty() {
+            let plural = if self.blocks.len() == 1 { "" } else { "s" };
+            write!(f, " + {} block{}", self.blocks.len(), plural)?;
+        }
+        if !self.row_cleared.is_empty() {
+            write!(f, " + row clear {}", letters(&self.row_cleared))?;
+        }
+        if !self.blasted.is_empty() {
+            write!(f, " + blast {}", letters(&self.blasted))?;
+        }
+        write!(f, " = {} x {} letters", self.letter_sum, self.length)?;
+        if self.multiplier > 1 {
+            write!(f, " x {}", self.multiplier)?;
+        }
+        write!(f, " = {}", self.score)
+    }
+}
+
+/// What `Board::find_words_with()` should look for
+#[derive(Clone, Copy, Debug)]
+pub struct WordSearch {
+    /// Only keep the best this many
+    pub top_n: usize,
+    /// Leave out any move that `is_commuted()`. This only loses nothing if our parent gets to
+    /// play every move we do, and the board it leads to survives, so it's best kept to searches
+    /// where neither -c nor -g is cutting anything off.
+    pub skip_commuted: bool,
+    /// Only keep one path for each word and set of tiles it destroys. Any others end up on the
+    /// same board with the same score, so they'd just take up room in `top_n`.
+    pub distinct_effects: bool,
+}
+
+/// What `Board::find_words_with()` turned up
+#[derive(Clone, Debug, Default)]
+pub struct FoundWords {
+    /// The best `top_n` moves
+    pub words: Vec<FoundWord>,
+    /// How many moves were left out by `WordSearch::skip_commuted`
+    pub commuted: usize,
+}
+
+impl WordSearch {
+    /// Just the best `top_n` words, nothing fancy
+    pub fn top(top_n: usize) -> Self {
+        WordSearch {
+            top_n,
+            skip_commuted: false,
+            distinct_effects: false,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq, DeepSizeOf)]
 pub struct Board {
     pub id: u64,
+    id_attempt: u32,
     width: usize,
     height: usize,
     min_word_length: usize,
-    tiles: [[char; Board::WIDTH]; Board::HEIGHT],
+    scoring: &'static Scoring,
+    /// Each tile as a single byte, see `Tile::code()`: a letter, `Board::BLOCK` or `Board::EMPTY`
+    tiles: Tiles,
     pub usable_tiles: usize,
     multipliers: Vec<Position>,
     cumulative_score: u32,
     searched: bool,
+    /// How many of our moves the search left for a sibling to play, see `set_found()`
+    commuted: usize,
     words: Vec<FoundWord>,
     evolved_via: Option<FoundWord>,
     evolved_from: Option<u64>,
+    /// Columns (as bits) touched by the move that made this board, see `footprint()`
+    evolved_footprint: u16,
+    /// Every board we've evolved into, and what the move there scored
+    children: Vec<(u64, u32)>,
     cleaned: bool,
 }
 
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
+        writeln!(
             f,
-            "id={}, came via = {}\n",
+            "id={}, came via = {}",
             self.id,
-            self.evolved_via.as_ref().unwrap().word
+            self.evolved_via
+                .as_ref()
+                .map_or("a better route", |via| &via.word)
         )?;
         for row in 0..=self.height {
             for col in 0..=self.width {
                 let c = self.tiles.get(row).unwrap().get(col).unwrap();
-                write!(f, "{}", c)?;
+                write!(f, "{}", Tile::from_code(*c))?;
             }
             if row != self.height {
-                write!(f, "\n")?;
+                writeln!(f)?;
             }
         }
         Ok(())
@@ -105,63 +176,127 @@ impl fmt::Display for Position {
     }
 }
 
+/// A whole board's worth of tiles, see `Board::tiles`
+type Tiles = [[u8; Board::WIDTH]; Board::HEIGHT];
+
 impl Board {
-    const MIN_WORD_LEN: usize = 3;
-    pub const WIDTH: usize = 9;
-    pub const HEIGHT: usize = 13;
+    pub const WIDTH: usize = bitboard::WIDTH;
+    pub const HEIGHT: usize = bitboard::HEIGHT;
+
+    /// Everything that affects how the rest of the game plays out goes into the id: the tiles, and
+    /// which of them still have a multiplier. `multipliers` needs to be sorted.
+    ///
+    /// Ids are used to break ties, so they need to be the same from run to run (and Rust version to
+    /// Rust version, and platform to platform) for results to be reproducible. That rules out
+    /// `DefaultHasher`, so instead it's a 64 bit FNV-1a over:
+    ///   - every tile, row by row, as the 4 little-endian bytes of each letter of its symbol as a
+    ///     `char`
+    ///   - the (row, col) byte pair of every multiplier
+    ///   - if we've had to `rekey()`, the 4 little-endian bytes of the attempt number
+    fn _hash_for(tiles: &Tiles, multipliers: &[Position], attempt: u32) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut feed = |bytes: &[u8]| {
+            for b in bytes {
+                hash ^= *b as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        };
+
+        tiles
+            .iter()
+            .flatten()
+            .flat_map(|c| Tile::from_code(*c).symbol().chars())
+            .for_each(|c| feed(&(c as u32).to_le_bytes()));
+        multipliers.iter().for_each(|m| feed(&[m.row, m.col]));
+        if attempt > 0 {
+            feed(&attempt.to_le_bytes());
+        }
+        hash
+    }
 
-    fn _hash_for(tiles: &[[char; Board::WIDTH]; Board::HEIGHT]) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        tiles.hash(&mut hasher);
-        hasher.finish()
+    fn get_usable_tiles(tiles: &Tiles) -> usize {
+        Board::usable_mask(tiles).count_ones() as usize
     }
 
-    fn get_usable_tiles(tiles: &[[char; Board::WIDTH]; Board::HEIGHT]) -> usize {
+    /// Every cell that has a letter in it
+    fn usable_mask(tiles: &Tiles) -> CellMask {
         tiles
             .iter()
-            .map(|r| {
-                r.iter()
-                    .filter(|c| **c != Board::BLOCK && **c != Board::EMPTY)
-                    .count()
-            })
-            .sum::<usize>()
+            .flatten()
+            .enumerate()
+            .filter(|(_, c)| **c != Board::BLOCK_TILE && **c != Board::EMPTY_TILE)
+            .fold(0, |mask, (idx, _)| mask | bitboard::bit(idx))
     }
 
-    pub fn new_from(
-        tiles: [[char; 9]; 13],
+    /// `tiles` can be anything that makes a `Tile`: `Tile`s from an `InputBoard`, or plain `char`s
+    pub fn new_from<T: Into<Tile>>(
+        tiles: [[T; 9]; 13],
         multipliers: Vec<(usize, usize)>,
         min_word_length: usize,
     ) -> Self {
         let height = tiles.len() - 1;
-        let width = tiles.get(0).unwrap().len() - 1;
+        let width = tiles.first().unwrap().len() - 1;
+        let tiles = tiles.map(|row| row.map(|t| t.into().code()));
+        let mut multipliers = multipliers
+            .iter()
+            .map(|p| Position::new(p.0, p.1))
+            .collect::<Vec<Position>>();
+        multipliers.sort();
 
         Self {
-            id: Board::_hash_for(&tiles),
+            id: Board::_hash_for(&tiles, &multipliers, 0),
+            id_attempt: 0,
             width,
             height,
             min_word_length,
+            scoring: Scoring::standard(),
             usable_tiles: Self::get_usable_tiles(&tiles),
             tiles,
-            multipliers: multipliers
-                .iter()
-                .map(|p| Position::new(p.0, p.1))
-                .collect(),
+            multipliers,
             words: vec![],
             cumulative_score: 0,
             evolved_via: Some(FoundWord {
                 path: vec![],
                 word: "created by God".to_string(),
                 score: 0,
+                paths: 1,
             }),
             evolved_from: Some(0),
+            evolved_footprint: 0,
+            children: vec![],
             searched: false,
+            commuted: 0,
             cleaned: false,
         }
     }
 
+    /// Score moves with `scoring` rather than the standard values
+    pub fn with_scoring(mut self, scoring: &'static Scoring) -> Self {
+        self.scoring = scoring;
+        self
+    }
+
     pub const BLOCK: char = '.';
     pub const EMPTY: char = ' ';
     pub const DEBUG: char = '*';
+    const BLOCK_TILE: u8 = Board::BLOCK as u8;
+    const EMPTY_TILE: u8 = Board::EMPTY as u8;
+
+    /// Do we represent the same game state as `other`? Ids are just a hash of this, so two boards
+    /// with the same id *should* be the same, but it's not guaranteed.
+    pub fn same_state(&self, other: &Board) -> bool {
+        self.tiles == other.tiles && self.multipliers == other.multipliers
+    }
+
+    /// Our id collided with a different board's, so move on to the next one. This is deterministic
+    /// so the same tiles always walk through the same sequence of ids.
+    pub fn rekey(&mut self) {
+        self.id_attempt += 1;
+        self.id = Board::_hash_for(&self.tiles, &self.multipliers, self.id_attempt);
+    }
 
     pub fn get_score(&self) -> u32 {
         self.cumulative_score
@@ -182,6 +317,32 @@ impl Board {
     pub fn set_words(&mut self, words: Vec<FoundWord>) {
         self.words = words;
         self.searched = true;
+        self.commuted = 0;
+    }
+
+    /// `set_words()` for a search that might have left moves out with --skip-commuted. If it
+    /// left every one of them out we have no words, but we're not the end of the game.
+    pub fn set_found(&mut self, found: FoundWords) {
+        self.set_words(found.words);
+        self.commuted = found.commuted;
+    }
+
+    pub fn commuted(&self) -> usize {
+        self.commuted
+    }
+
+    /// Another parent has turned up for us. Which moves we can leave to a sibling depends on the
+    /// move that made us, and now there are two of those, so stop leaving any out. If we already
+    /// have, we need searching again: returns whether we do.
+    pub fn forget_footprint(&mut self) -> bool {
+        self.evolved_footprint = 0;
+        if !self.searched || self.commuted == 0 {
+            return false;
+        }
+        self.words = vec![];
+        self.searched = false;
+        self.commuted = 0;
+        true
     }
 
     pub fn dirty(&self) -> bool {
@@ -192,6 +353,8 @@ impl Board {
         if !self.cleaned {
             // Now that the board has been fully processed, free up some memory
             // self.tiles = []; -- can't "clean" an array ; it'll always be that many characters
+            // `children` has to stay: if a better route to us turns up later, `improve_route()`
+            // walks it to push the new score down to everything we evolved into.
             self.words = vec![];
             self.cleaned = true;
         }
@@ -205,113 +368,202 @@ impl Board {
         self.evolved_from.unwrap()
     }
 
-    pub fn get(&self, pos: &Position) -> char {
-        self.tiles[pos.row as usize][pos.col as usize]
+    /// Every board we've evolved into, along with what the move there scored.
+    /// Unlike `words` these survive `clean()` since we need them to push score
+    /// improvements down to our descendants.
+    pub fn children(&self) -> &Vec<(u64, u32)> {
+        &self.children
     }
 
-    fn _get(tiles: &[[char; Board::WIDTH]; Board::HEIGHT], pos: &Position) -> char {
-        tiles[pos.row as usize][pos.col as usize]
+    pub fn add_child(&mut self, child_id: u64, score: u32) {
+        // A board searched again (see `forget_footprint()`) makes some of the same children again
+        if !self.children.contains(&(child_id, score)) {
+            self.children.push((child_id, score));
+        }
     }
 
-    fn find_path_of_destruction(&self, path: &Vec<Position>, word: &str) -> Vec<Position> {
-        let mut path_of_destruction = Vec::with_capacity(word.len() * 3);
-        path_of_destruction.extend(path.clone());
+    /// Would arriving here from `from` with a total of `score` be a better
+    /// route than the one we already know about? Ties go to the lowest parent
+    /// id so the answer doesn't depend on what order routes show up in.
+    pub fn is_better_route(&self, from: u64, score: u32) -> bool {
+        score > self.cumulative_score
+            || (score == self.cumulative_score && from < self.evolved_from())
+    }
 
-        // See if we're *directly* going over any of the row-clearing letters
-        path_of_destruction.extend(
-            word.char_indices()
-                .filter_map(|(idx, c)| {
-                    if c != 'j' && c != 'q' && c != 'x' && c != 'z' {
-                        return None;
-                    }
+    /// Point this board at a new (better) parent. Without `via` we keep the move we've got if
+    /// the parent is the same one, and forget it if it isn't (see `move_from()`).
+    pub fn relink(&mut self, from: u64, via: Option<FoundWord>, score: u32) {
+        if via.is_some() || self.evolved_from != Some(from) {
+            self.evolved_via = via;
+        }
+        self.evolved_from = Some(from);
+        self.cumulative_score = score;
+    }
 
-                    let p = path.get(idx).unwrap();
-                    Some((0..=self.width).map(|c| Position {
-                        row: p.row,
-                        col: c as u8,
-                    }))
-                })
-                .flatten(),
-        );
+    /// The move that takes `parent` (the board we evolved from) to us. Boards don't keep the move
+    /// for every way into them, so if `relink()` has forgotten ours, search `parent` for it again.
+    pub fn move_from(&self, parent: &Board, dict: &Dictionary) -> FoundWord {
+        if let Some(via) = &self.evolved_via {
+            return via.clone();
+        }
+        parent
+            .find_words(dict, usize::MAX)
+            .into_iter()
+            .find(|found_word| {
+                parent.get_score() + found_word.score == self.get_score()
+                    && parent.evolve_via(found_word.clone()).same_state(self)
+            })
+            .expect("Our parent has to have a move that makes us")
+    }
 
-        // Any blocks get destroyed if any block adjacent to them is destroyed
-        path_of_destruction.extend(
-            path.iter()
-                .map(|p| {
-                    p.cardinal_neighbors(self.width, self.height)
-                        .into_iter()
-                        .filter(|p| self.get(p) == Board::BLOCK)
-                })
-                .flatten(),
-        );
+    pub fn get(&self, pos: &Position) -> Tile {
+        Tile::from_code(self.tiles[pos.row as usize][pos.col as usize])
+    }
 
-        if path.len() >= 5 {
-            path_of_destruction.extend(
-                path.iter()
-                    .map(|p| p.cardinal_neighbors(self.width, self.height))
-                    .flatten(),
-            );
-        }
+    fn tile(&self, idx: usize) -> Tile {
+        Tile::from_code(self.tiles[idx / Board::WIDTH][idx % Board::WIDTH])
+    }
 
-        HashSet::<Position>::from_iter(path_of_destruction.into_iter())
-            .drain()
-            .collect()
+    /// Can `pos` be used as part of a word?
+    pub fn is_usable(&self, pos: &Position) -> bool {
+        let c = self.get(pos);
+        c != Board::BLOCK && c != Board::EMPTY
+    }
+
+    /// Every position on the board, top left to bottom right
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..=self.height)
+            .flat_map(move |row| (0..=self.width).map(move |col| Position::new(row, col)))
+    }
+
+    /// Every position next to `pos`, diagonals included
+    pub fn neighbors(&self, pos: &Position) -> impl Iterator<Item = Position> {
+        NEIGHBORS[bitboard::index_of(pos)]
+            .as_slice()
+            .iter()
+            .map(|&idx| bitboard::position_of(idx as usize))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn min_word_length(&self) -> usize {
+        self.min_word_length
+    }
+
+    /// Whether `word` has enough letters to be played here. Letters, not bytes.
+    pub fn is_long_enough(&self, word: &str) -> bool {
+        word.chars().count() >= self.min_word_length
     }
 
-    fn destroy_board(&self, path_of_destruction: &Vec<Position>) -> [[char; Board::WIDTH]; Board::HEIGHT] {
-        let mut new_tiles = self.tiles.clone();
+    /// Positions of the multipliers that are still around
+    pub fn multipliers(&self) -> &Vec<Position> {
+        &self.multipliers
+    }
+
+    /// Every cell that playing a word along `path` blows up
+    fn destruction_mask(&self, path: &[Position]) -> CellMask {
+        let mut destroyed = path
+            .iter()
+            .fold(0, |mask, p| mask | bitboard::bit(bitboard::index_of(p)));
 
-        for p in path_of_destruction {
-            new_tiles[p.row as usize][p.col as usize] = Board::EMPTY;
+        // See if we're *directly* going over any of the row-clearing letters
+        for p in path {
+            if self.scoring.clears_row(self.get(p)) {
+                destroyed |= bitboard::row_mask(p.row as usize);
+            }
+        }
+
+        // Any blocks get destroyed if any block adjacent to them is destroyed
+        let around_path = path.iter().fold(0, |mask, p| {
+            mask | CARDINAL_NEIGHBORS[bitboard::index_of(p)]
+        });
+        destroyed |= around_path & self.block_mask();
+
+        // Counting letters, so a Qu tile counts for 2
+        let letters = path.iter().map(|p| self.get(p).letters()).sum::<usize>();
+        if letters >= self.scoring.blast_length {
+            destroyed |= around_path;
         }
 
-        new_tiles
+        destroyed
     }
 
-    fn apply_gravity(tiles: &mut [[char; Board::WIDTH]; Board::HEIGHT], path_of_destruction: &mut Vec<Position>) {
-        // Reverse sort based on row so we start at the lowest row and work our way back up
-        path_of_destruction.sort_by(|a, b| b.row.cmp(&a.row));
+    /// Every cell that has a block in it
+    fn block_mask(&self) -> CellMask {
+        self.tiles
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, c)| **c == Board::BLOCK_TILE)
+            .fold(0, |mask, (idx, _)| mask | bitboard::bit(idx))
+    }
+
+    /// Let everything fall into the gaps left by `destroyed`. Multipliers are stuck to their tiles
+    /// so they fall too.
+    ///
+    /// Only the rows from the lowest destroyed one up get to fall, in every column.
+    #[allow(clippy::needless_range_loop)]
+    fn apply_gravity(tiles: &mut Tiles, multipliers: &mut [Position], destroyed: CellMask) {
+        if destroyed == 0 {
+            return;
+        }
+        let lowest_row = (127 - destroyed.leading_zeros() as usize) / Board::WIDTH;
 
-        // No need to check row 0, doesn't matter if it's got blanks
-        // No need to start any lower than the first blown up row
-        for r in (1..=path_of_destruction[0].row).rev() {
-            for c in 0..tiles.get(0).unwrap().len() {
-                if tiles[r as usize][c as usize] != Board::EMPTY {
+        for col in 0..Board::WIDTH {
+            let mut land_on = lowest_row;
+            for row in (0..=lowest_row).rev() {
+                let tile = tiles[row][col];
+                if tile == Board::EMPTY_TILE {
                     continue;
                 }
 
-                for row in (0..=r - 1).rev() {
-                    let above = Board::_get(&tiles, &Position { row, col: c as u8 });
-                    if above == ' ' {
-                        continue;
+                if row != land_on {
+                    tiles[land_on][col] = tile;
+                    tiles[row][col] = Board::EMPTY_TILE;
+                    if let Some(m) = multipliers
+                        .iter_mut()
+                        .find(|m| m.row as usize == row && m.col as usize == col)
+                    {
+                        m.row = land_on as u8;
                     }
-
-                    tiles[r as usize][c as usize] = above.clone();
-                    tiles[row as usize][c as usize] = Board::EMPTY;
-                    break;
                 }
+                land_on = land_on.saturating_sub(1);
             }
         }
     }
 
     pub fn evolve_via(&self, found_word: FoundWord) -> Board {
-        let mut path_of_destruction =
-            self.find_path_of_destruction(&found_word.path, &found_word.word);
-        let mut new_tiles = self.destroy_board(&path_of_destruction);
-        Self::apply_gravity(&mut new_tiles, &mut path_of_destruction);
+        let destroyed = self.destruction_mask(&found_word.path);
+        let evolved_footprint = self.footprint(&found_word.path, destroyed);
 
-        let new_mults = self
+        let mut new_tiles = self.tiles;
+        for idx in bitboard::cells(destroyed) {
+            new_tiles[idx / Board::WIDTH][idx % Board::WIDTH] = Board::EMPTY_TILE;
+        }
+
+        let mut new_mults = self
             .multipliers
             .iter()
-            .filter(|p| !path_of_destruction.contains(p))
+            .filter(|p| destroyed & bitboard::bit(bitboard::index_of(p)) == 0)
             .cloned()
-            .collect();
+            .collect::<Vec<Position>>();
+
+        Self::apply_gravity(&mut new_tiles, &mut new_mults, destroyed);
+        new_mults.sort();
 
         Board {
-            id: Board::_hash_for(&new_tiles),
+            id: Board::_hash_for(&new_tiles, &new_mults, 0),
+            id_attempt: 0,
             width: self.width,
             height: self.height,
             min_word_length: self.min_word_length,
+            scoring: self.scoring,
             usable_tiles: Self::get_usable_tiles(&new_tiles),
             tiles: new_tiles,
             multipliers: new_mults,
@@ -319,189 +571,551 @@ impl Board {
             words: vec![],
             evolved_via: Some(found_word),
             evolved_from: Some(self.id),
+            evolved_footprint,
+            children: vec![],
             searched: false,
+            commuted: 0,
             cleaned: false,
         }
     }
 
     pub fn is_terminal(&self) -> bool {
         assert!(self.searched, "idk if I'm terminal, nobody's looked!");
-        self.words.len() == 0
+        self.words.is_empty() && self.commuted == 0
+    }
+
+    /// Every column a move could change, or be changed by, as bits: everything it destroys, plus
+    /// the columns either side of its path (that's where it looks for blocks to blow up).
+    ///
+    /// Two moves whose footprints don't overlap can't affect each other at all, so playing them in
+    /// either order ends up on the same board with the same score.
+    fn footprint(&self, path: &[Position], destroyed: CellMask) -> u16 {
+        let path_cols = path.iter().fold(0_u16, |cols, p| cols | 1 << p.col);
+        let destroyed_cols =
+            bitboard::cells(destroyed).fold(0_u16, |cols, idx| cols | 1 << (idx % Board::WIDTH));
+        let all_cols = (1_u16 << (self.width + 1)) - 1;
+
+        (destroyed_cols | path_cols | path_cols << 1 | path_cols >> 1) & all_cols
+    }
+
+    /// Whether playing `found_word` here just repeats, in the other order, a pair of moves we'll
+    /// also play from our parent. If the move that made us and `found_word` don't touch each other
+    /// we only want one order of them, and the one we keep starts furthest left.
+    fn is_commuted(&self, found_word: &FoundWord) -> bool {
+        if self.evolved_footprint == 0 {
+            return false;
+        }
+
+        let destroyed = self.destruction_mask(&found_word.path);
+        let footprint = self.footprint(&found_word.path, destroyed);
+        footprint & self.evolved_footprint == 0
+            && footprint.trailing_zeros() < self.evolved_footprint.trailing_zeros()
     }
 
     pub fn find_words(&self, dict: &Dictionary, top_n: usize) -> Vec<FoundWord> {
+        self.find_words_with(dict, WordSearch::top(top_n)).words
+    }
+
+    pub fn find_words_with(&self, dict: &Dictionary, search: WordSearch) -> FoundWords {
         let mut found_words = Vec::new();
-        for row in 0..self.height + 1 {
-            for col in 0..self.width + 1 {
-                let start = Position::new(row, col);
-                if self.tiles[row][col] == Board::EMPTY || self.tiles[row][col] == Board::BLOCK {
-                    // No words start with a space, or can start on a blocked tile. Skip them.
-                    continue;
-                }
 
-                let found = self.finds_words_in_starting_from(dict, start);
-                found_words.extend(found);
-            }
+        // No words start with a space, or can start on a blocked tile
+        let usable = Board::usable_mask(&self.tiles);
+        for start in bitboard::cells(usable) {
+            self.find_words_starting_from(dict, start, usable, &mut found_words);
+        }
+
+        let mut commuted = 0;
+        if search.skip_commuted {
+            let before = found_words.len();
+            found_words.retain(|found_word| !self.is_commuted(found_word));
+            commuted = before - found_words.len();
+        }
+
+        if search.distinct_effects {
+            found_words = self.group_by_effect(found_words);
         }
 
+        let top_n = search.top_n;
         if found_words.len() > top_n {
             /*
             Since we have too many, we need to pick some. Sort & truncate.
             Sorting order is:
               - score ; highest wins
               - word length ; shortest wins
-              - word alphabetically ; can't have a tie, no dupe words
+              - word alphabetically
+              - path ; the same word can show up in a few places, can't have a tie after this
             */
             found_words.sort_by(|a, b| {
                 a.score
                     .cmp(&b.score)
                     .reverse()
                     .then(a.word.len().cmp(&b.word.len()).then(a.word.cmp(&b.word)))
+                    .then_with(|| a.path.cmp(&b.path))
             });
 
             found_words.truncate(top_n);
         }
-        found_words
+        FoundWords {
+            words: found_words,
+            commuted,
+        }
     }
 
-    fn finds_words_in_starting_from(&self, dict: &Dictionary, start: Position) -> Vec<FoundWord> {
-        let mut path = Vec::with_capacity(16);
-        path.push(start.clone());
+    /// Fold together any `found_words` that spell the same word and destroy the same tiles,
+    /// keeping the best-scoring one (a multiplier can be on one path and not the other) and
+    /// counting how many there were. Equal scores go to the lowest path.
+    fn group_by_effect(&self, found_words: Vec<FoundWord>) -> Vec<FoundWord> {
+        let mut grouped: Vec<FoundWord> = Vec::with_capacity(found_words.len());
+        let mut group_idx: HashMap<(String, CellMask), usize> = HashMap::new();
+        for found_word in found_words {
+            let effect = self.destruction_mask(&found_word.path);
+            match group_idx.entry((found_word.word.clone(), effect)) {
+                Entry::Occupied(entry) => {
+                    let group = &mut grouped[*entry.get()];
+                    let paths = group.paths + found_word.paths;
+                    if (found_word.score, &group.path) > (group.score, &found_word.path) {
+                        *group = found_word;
+                    }
+                    group.paths = paths;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(grouped.len());
+                    grouped.push(found_word);
+                }
+            }
+        }
+        grouped
+    }
 
-        let path_str = String::from(self.get(&Position {
-            row: start.row,
-            col: start.col,
-        }));
-        self._find_word(&start, &path, &path_str, dict)
+    fn find_words_starting_from(
+        &self,
+        dict: &Dictionary,
+        start: usize,
+        usable: CellMask,
+        found_words: &mut Vec<FoundWord>,
+    ) {
+        let mut path = Vec::with_capacity(16);
+        let mut path_str = String::with_capacity(16);
+
+        path.push(bitboard::position_of(start));
+        self.tile(start).push_onto(&mut path_str);
+        self._find_word(
+            start,
+            bitboard::bit(start),
+            usable,
+            &mut path,
+            &mut path_str,
+            dict,
+            found_words,
+        );
     }
 
+    /// We have arrived at cell `idx`, having been through every cell in `visited`, spelling out
+    /// `path_str`. From here we:
+    ///   1. Figure out if path_str counts as a complete word, add path + word to our results
+    ///   2. Find candidate cells (not in our path, and not blocked or empty)
+    ///   3. Filter out candidate cells where path_str + their letter is not part of a word
+    ///   4. Recurse into each candidate that's left
+    ///
+    /// `path` and `path_str` are shared all the way down, every step undoes what it adds to them.
+    #[allow(clippy::too_many_arguments)]
     fn _find_word(
         &self,
-        pos: &Position,
-        path: &Vec<Position>,
-        path_str: &String,
+        idx: usize,
+        visited: CellMask,
+        usable: CellMask,
+        path: &mut Vec<Position>,
+        path_str: &mut String,
         dict: &Dictionary,
-    ) -> Vec<FoundWord> {
-        /*
-        We have arrived at pos. From here we need to
-            1. Figure out if path_str counts as a complete word, add path + word to our list of results
-            2. Find candidate positions (not in our path, and not blocked)
-            3. Filter out candidate positions where path_str + their value is not part of a word
-            4. For each candidate: recurse and add their list of words to ours
-            5. Return a flattened version of our list of results
-        */
-        let mut found_words: Vec<FoundWord> = Vec::new();
-
-        if path_str.len() >= Board::MIN_WORD_LEN && dict.is_word(&path_str) {
+        found_words: &mut Vec<FoundWord>,
+    ) {
+        // A tile can have more than one letter on, so the path can be shorter than the word
+        if (path.len() >= self.min_word_length || self.is_long_enough(path_str))
+            && dict.is_word(path_str)
+        {
             found_words.push(FoundWord {
                 path: path.clone(),
-                word: path_str.clone(),
+                word: path_str.to_string(),
                 score: self.score_for(path_str, path),
+                paths: 1,
             });
         }
 
-        let candidate_positions = pos.neighbors(self.width, self.height);
+        for &next in NEIGHBORS[idx].as_slice() {
+            let next = next as usize;
+            let next_bit = bitboard::bit(next);
 
-        for p in candidate_positions.iter() {
-            // Can't cross our existing path
-            if path.contains(p) {
+            // Can't cross our existing path, and blocks and gaps are dead-ends
+            if visited & next_bit != 0 || usable & next_bit == 0 {
                 continue;
             }
 
-            let l = self.get(p);
-
-            if l == Board::BLOCK || l == Board::EMPTY {
-                // This tile is a dead-end, no need to keep looking
-                continue;
+            let len = path_str.len();
+            self.tile(next).push_onto(path_str);
+            if dict.has_path(path_str) {
+                path.push(bitboard::position_of(next));
+                self._find_word(
+                    next,
+                    visited | next_bit,
+                    usable,
+                    path,
+                    path_str,
+                    dict,
+                    found_words,
+                );
+                path.pop();
             }
+            path_str.truncate(len);
+        }
+    }
 
-            let mut fragment = String::with_capacity(path_str.len() + 1);
-            fragment.push_str(&path_str);
-            fragment.push_str(&l.to_string());
+    /// How many points playing `word` along `path` is worth. Words too short to play aren't
+    /// worth anything.
+    pub fn score_for(&self, word: &str, path: &[Position]) -> u32 {
+        if !self.is_long_enough(word) {
+            return 0;
+        }
 
-            if dict.has_path(&fragment) {
-                let mut next_path = Vec::with_capacity(path.len() + 1);
-                next_path.clone_from(path);
-                next_path.push(p.clone());
+        // Base score is the sum of _all_ the letter values
+        let base_score = bitboard::cells(self.destruction_mask(path))
+            .map(|idx| self.scoring.tile_value(self.tile(idx)))
+            .sum::<u32>();
 
-                let found = self._find_word(&p, &next_path, &fragment, dict);
-                if !found.is_empty() {
-                    found_words.extend(found);
-                }
-            }
+        /*
+          Multipliers add up rather than multiply: each one the path goes over is worth
+          `scoring.multiplier` (2 in the standard game), so using two of them is 2 * 2 = 4 and
+          using three is 6. If you don't use any of them your multiplier is 1, which does nothing
+        */
+        let used = path.iter().filter(|p| self.multipliers.contains(p)).count() as u32;
+        let multiplier = std::cmp::max(self.scoring.multiplier * used, 1);
+
+        (base_score * word.chars().count() as u32) * multiplier
+    }
+
+    /// `score_for()`, showing its working: which tiles playing `word` along `path` destroys and
+    /// why, and how they add up to the score.
+    pub fn explain_score(&self, word: &str, path: &[Position]) -> ScoreBreakdown {
+        let destroyed = self.destruction_mask(path);
+        let on_path = path
+            .iter()
+            .fold(0, |mask, p| mask | bitboard::bit(bitboard::index_of(p)));
+        let cleared_rows = path
+            .iter()
+            .filter(|p| self.scoring.clears_row(self.get(p)))
+            .fold(0, |mask, p| mask | bitboard::row_mask(p.row as usize));
+
+        let letters = Board::usable_mask(&self.tiles);
+        let letters_in = |mask: CellMask| {
+            bitboard::cells(mask & letters)
+                .map(|idx| (bitboard::position_of(idx), self.tile(idx)))
+                .collect::<Vec<(Position, Tile)>>()
+        };
+
+        let multipliers = path.iter().filter(|p| self.multipliers.contains(p)).count() as u32;
+        let multiplier = std::cmp::max(self.scoring.multiplier * multipliers, 1);
+        let length = word.chars().count() as u32;
+        let letter_sum = bitboard::cells(destroyed)
+            .map(|idx| self.scoring.tile_value(self.tile(idx)))
+            .sum::<u32>();
+
+        ScoreBreakdown {
+            word: word.to_string(),
+            path: path.iter().map(|p| (p.clone(), self.get(p))).collect(),
+            row_cleared: letters_in(destroyed & cleared_rows & !on_path),
+            blasted: letters_in(destroyed & !cleared_rows & !on_path),
+            blocks: bitboard::cells(destroyed & self.block_mask())
+                .map(bitboard::position_of)
+                .collect(),
+            letter_sum,
+            length,
+            multipliers,
+            multiplier,
+            score: self.score_for(word, path),
         }
+    }
 
-        found_words.shrink_to_fit();
-        found_words
+    /// What `tile` is worth on this board
+    pub fn tile_score(&self, tile: Tile) -> u32 {
+        self.scoring.tile_value(tile)
     }
 
-    fn score_for(&self, word: &str, path: &Vec<Position>) -> u32 {
-        // Base score is the sum of _all_ the letter values
-        let base_score = self
-            .find_path_of_destruction(path, word)
+    /// An optimistic guess at how many more points this board could possibly give up, no matter
+    /// how it's played. It's never an underestimate, so if `get_score()` plus this can't beat
+    /// something we've already got there's no point looking any further.
+    ///
+    /// Every tile can only be destroyed once, so across all the remaining moves the letter sums
+    /// add up to at most the value of every letter left on the board. No move can be longer than
+    /// the longest word (or the number of letters left) and no move can use more multipliers than
+    /// there are left.
+    pub fn upper_bound(&self, longest_word: usize) -> u32 {
+        // More than the number of tiles if any of them have more than one letter on
+        let letters_on_board = bitboard::cells(Board::usable_mask(&self.tiles))
+            .map(|idx| self.tile(idx).letters())
+            .sum::<usize>();
+        if letters_on_board < self.min_word_length {
+            return 0;
+        }
+
+        let letters_left = self
+            .tiles
             .iter()
-            .map(|p| {
-                let our_letter = self.get(p);
-                if our_letter == '.' || our_letter == ' ' {
-                    return 0;
-                }
-                let our_letter_num = our_letter as usize;
-                LETTER_SCORES[our_letter_num - 97]
-            })
+            .flatten()
+            .map(|c| self.scoring.tile_value(Tile::from_code(*c)))
             .sum::<u32>();
+        let longest_move = std::cmp::min(longest_word, letters_on_board) as u32;
+        let best_multiplier =
+            std::cmp::max(self.scoring.multiplier * self.multipliers.len() as u32, 1);
 
-        /*
-          Multipliers stack and in tower mode are only ever 2x. So if you use
-          one of them the multiplier is 2. If you use two of them it's 4.
-          If you don't use any of them your multiplier is 1, which does nothing
-        */
-        let multiplier = std::cmp::max(
-            path.iter()
-                .map(|p| if self.multipliers.contains(p) { 2 } else { 0 })
-                .sum::<u32>(),
-            1,
+        letters_left * longest_move * best_multiplier
+    }
+}
+
+#[cfg(test)]
+mod word_search_tests {
+    use super::*;
+
+    fn bottom_row_board(letters: &str) -> Board {
+        let mut tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+        for (col, c) in letters.chars().enumerate() {
+            tiles[Board::HEIGHT - 1][col] = c;
+        }
+        Board::new_from(tiles, vec![], 3)
+    }
+
+    fn play(board: &Board, dict: &Dictionary, word: &str) -> Board {
+        let found_word = board
+            .find_words(dict, usize::MAX)
+            .into_iter()
+            .find(|w| w.word == word)
+            .unwrap();
+        board.evolve_via(found_word)
+    }
+
+    fn canonical_words(board: &Board, dict: &Dictionary) -> Vec<String> {
+        board
+            .find_words_with(
+                dict,
+                WordSearch {
+                    skip_commuted: true,
+                    ..WordSearch::top(usize::MAX)
+                },
+            )
+            .words
+            .into_iter()
+            .map(|w| w.word)
+            .collect()
+    }
+
+    #[test]
+    /// -w decides what the search turns up and what scores, whatever the dictionary holds
+    fn min_word_length_is_honoured() {
+        let words = ["ab", "abc", "abcd", "abcde", "abcdef"];
+        let dict = Dictionary::from_words(words, 1);
+        let mut tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+        for (col, c) in "abcdef".chars().enumerate() {
+            tiles[Board::HEIGHT - 1][col] = c;
+        }
+
+        for min_word_length in 3..=6 {
+            let board = Board::new_from(tiles, vec![], min_word_length);
+            let found = board
+                .find_words(&dict, usize::MAX)
+                .into_iter()
+                .map(|w| w.word)
+                .collect::<Vec<String>>();
+            assert_eq!(
+                found,
+                words[min_word_length - 2..],
+                "-w {}",
+                min_word_length
+            );
+
+            let path = (0..min_word_length - 1)
+                .map(|col| Position::new(Board::HEIGHT - 1, col))
+                .collect::<Vec<Position>>();
+            assert_eq!(board.score_for(words[min_word_length - 3], &path), 0);
+
+            // Same again with the dictionary doing the filtering
+            let dict = Dictionary::from_words(words, min_word_length);
+            assert_eq!(board.find_words(&dict, usize::MAX).len(), found.len());
+        }
+    }
+
+    #[test]
+    /// "cat" and "dog" are far enough apart not to matter to each other, so only "cat" then "dog"
+    /// gets played
+    fn far_apart_moves_only_go_one_way() {
+        let dict = Dictionary::from_words(["cat", "dog"], 3);
+        let start = bottom_row_board("cat  dog");
+
+        assert_eq!(canonical_words(&start, &dict), vec!["cat", "dog"]);
+        assert_eq!(
+            canonical_words(&play(&start, &dict, "cat"), &dict),
+            vec!["dog"]
+        );
+        assert!(canonical_words(&play(&start, &dict, "dog"), &dict).is_empty());
+    }
+
+    #[test]
+    /// After "dog", "cat" is still there to play, it's just played from the start instead. That
+    /// doesn't make the board the end of the game.
+    fn commuted_boards_are_not_terminal() {
+        let dict = Dictionary::from_words(["cat", "dog"], 3);
+        let mut after_dog = play(&bottom_row_board("cat  dog"), &dict, "dog");
+        let found = after_dog.find_words_with(
+            &dict,
+            WordSearch {
+                skip_commuted: true,
+                ..WordSearch::top(usize::MAX)
+            },
+        );
+        assert!(found.words.is_empty());
+        assert_eq!(found.commuted, 1);
+
+        after_dog.set_found(found);
+        assert!(after_dog.searched());
+        assert_eq!(after_dog.commuted(), 1);
+        assert!(!after_dog.is_terminal());
+
+        let mut finished = play(&after_dog, &dict, "cat");
+        let found = finished.find_words_with(
+            &dict,
+            WordSearch {
+                skip_commuted: true,
+                ..WordSearch::top(usize::MAX)
+            },
+        );
+        assert_eq!(found.commuted, 0);
+        finished.set_words(found.words);
+        assert!(finished.is_terminal());
+    }
+
+    #[test]
+    /// Forwards and backwards along the same tiles is the same move, but the other "tat" isn't
+    fn paths_with_the_same_effect_are_grouped() {
+        let dict = Dictionary::from_words(["tat"], 3);
+        let start = bottom_row_board("tat tat");
+        assert_eq!(start.find_words(&dict, usize::MAX).len(), 4);
+
+        let grouped = start
+            .find_words_with(
+                &dict,
+                WordSearch {
+                    distinct_effects: true,
+                    ..WordSearch::top(usize::MAX)
+                },
+            )
+            .words;
+        assert_eq!(grouped.len(), 2);
+        for found_word in grouped {
+            assert_eq!(found_word.paths, 2);
+            assert!(found_word.path.first() < found_word.path.last());
+        }
+    }
+
+    #[test]
+    /// Either "zaa" clears the whole row, but only one of them goes over the multiplier, and
+    /// that's the one worth keeping
+    fn grouping_keeps_the_best_score() {
+        let dict = Dictionary::from_words(["zaa"], 3);
+        let mut tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+        for (col, c) in "aazaa".chars().enumerate() {
+            tiles[Board::HEIGHT - 1][col] = c;
+        }
+        let start = Board::new_from(tiles, vec![(Board::HEIGHT - 1, 4)], 3);
+        let found = start.find_words(&dict, usize::MAX);
+        assert_eq!(found.len(), 2);
+        let best = found.iter().max_by_key(|w| w.score).unwrap();
+
+        let grouped = start
+            .find_words_with(
+                &dict,
+                WordSearch {
+                    distinct_effects: true,
+                    ..WordSearch::top(usize::MAX)
+                },
+            )
+            .words;
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped[0].paths, 2);
+        assert_eq!(grouped[0].score, best.score);
+        assert_eq!(grouped[0].path, best.path);
+        assert_eq!(
+            grouped[0].score,
+            start.score_for("zaa", &best.path),
+            "path and score go together"
         );
+    }
 
-        (base_score * word.len() as u32) * multiplier
+    #[test]
+    /// Right next to each other they could interact, so both orders get played
+    fn neighbouring_moves_go_both_ways() {
+        let dict = Dictionary::from_words(["cat", "dog"], 3);
+        let start = bottom_row_board("cat dog");
+
+        assert_eq!(
+            canonical_words(&play(&start, &dict, "cat"), &dict),
+            vec!["dog"]
+        );
+        assert_eq!(
+            canonical_words(&play(&start, &dict, "dog"), &dict),
+            vec!["cat"]
+        );
     }
 }
 
-/*
 #[cfg(test)]
 mod board_tests {
     use super::*;
 
-    /// Turns 1+ strings into a Vec<Vec<String>> suitable for passing to Board::new_from()
+    /// Turns 1+ strings into the tiles for Board::new_from(), sitting at the bottom of the board
     macro_rules! to_board {
         ($($x:expr), *) => {
             {
-                vec![
-                    $(
-                        $x.chars().map(|c| c.to_string()).collect()
-                    ), *
-                ]
+                let rows: Vec<&str> = vec![$($x), *];
+                let mut tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+                let first_row = Board::HEIGHT - rows.len();
+                for (r, row) in rows.iter().enumerate() {
+                    for (col, c) in row.chars().enumerate() {
+                        tiles[first_row + r][col] = c;
+                    }
+                }
+                tiles
             }
         };
     }
 
-    /// Turns 1+ tuples of (row, col) into a Vec<Position>
+    /// Turns 1+ tuples of (row, col) into a Vec<Position>. Rows count from the top of a
+    /// `to_board!` with `$rows` rows in it.
     macro_rules! to_path {
-        ($($x:expr), *) => {
+        ($rows:expr; $($x:expr), *) => {
             {
                 vec![
                     $(
-                        Position::at($x.0, $x.1)
+                        Position::new(Board::HEIGHT - $rows + $x.0, $x.1)
                     ), *
                 ]
             }
         };
     }
 
+    /// The bottom `n` rows of `b`, as strings
+    fn bottom_rows(b: &Board, n: usize) -> Vec<String> {
+        (Board::HEIGHT - n..Board::HEIGHT)
+            .map(|row| {
+                (0..Board::WIDTH)
+                    .map(|col| b.get(&Position::new(row, col)).symbol())
+                    .collect()
+            })
+            .collect()
+    }
+
     #[test]
     /// A three letter word, other letters don't count
     fn simple_three() {
         let b = Board::new_from(to_board!("vis", "asd"), vec![], 3);
-        let path = to_path![(0, 0), (0, 1), (0, 2)];
+        let path = to_path![2; (0, 0), (0, 1), (0, 2)];
         assert_eq!(b.score_for("vis", &path), 21);
     }
 
@@ -511,7 +1125,7 @@ mod board_tests {
     /// letters
     fn clearing_three() {
         let b = Board::new_from(to_board!("seezahbep", "fnsoobksl"), vec![], 3);
-        let path = to_path![(0, 3), (1, 3), (1, 4)];
+        let path = to_path![2; (0, 3), (1, 3), (1, 4)];
         assert_eq!(b.score_for("zoo", &path), 93);
     }
 
@@ -519,7 +1133,7 @@ mod board_tests {
     /// A four letter word, other letters don't count
     fn simple_four() {
         let b = Board::new_from(to_board!("sign", "asdf"), vec![], 3);
-        let path = to_path![(0, 0), (0, 1), (0, 2), (0, 3)];
+        let path = to_path![2; (0, 0), (0, 1), (0, 2), (0, 3)];
         assert_eq!(b.score_for("sign", &path), 28);
     }
 
@@ -527,22 +1141,18 @@ mod board_tests {
     /// A five letter word with three adjacent letters, a space and a block
     fn simple_five() {
         let b = Board::new_from(to_board!("lho .", "nodes"), vec![], 3);
-        let path = to_path![(1, 0), (1, 1), (1, 2), (1, 3), (1, 4)];
+        let path = to_path![2; (1, 0), (1, 1), (1, 2), (1, 3), (1, 4)];
         assert_eq!(b.score_for("nodes", &path), 80);
     }
 
     #[test]
     /// Here we have a word where there's a line-clearing letter getting
-    /// destructed. Make sure that line-clearing `j` doesn't cause the `v` to
-    /// get consumed as well. It shouldn't since it's not cardinally-adjacent
-    /// to any of the tiles in the word.
+    /// destructed. Make sure that line-clearing `j` doesn't cause the `v` on
+    /// its row to get consumed as well. It shouldn't since the `j` isn't on the
+    /// path, it's just cardinally-adjacent to it.
     fn bonus_letters_dont_clear() {
-        let b = Board::new_from(
-            to_board!(".ulsalidc.", "oprincess.", "..bm..j..v"),
-            vec![],
-            3,
-        );
-        let path = to_path![
+        let b = Board::new_from(to_board!(".ulsalidc", "oprincess", "v.bm..j.."), vec![], 3);
+        let path = to_path![3;
             (1, 1),
             (1, 2),
             (1, 3),
@@ -555,6 +1165,216 @@ mod board_tests {
         assert_eq!(b.score_for("princess", &path), 392);
     }
 
+    #[test]
+    /// From scoring.md: mux + tqbspi = 114. The `x` is on the path, so its whole row goes.
+    fn golden_mux() {
+        let b = Board::new_from(to_board!("tqmuxbspi", "aaaaaaaaa"), vec![], 3);
+        let path = to_path![2; (0, 2), (0, 3), (0, 4)];
+        assert_eq!(b.score_for("mux", &path), 114);
+
+        let explained = b.explain_score("mux", &path);
+        assert_eq!(explained.score, 114);
+        assert!(explained.blasted.is_empty());
+        assert_eq!(
+            explained.to_string(),
+            "mux + row clear tqbspi = 38 x 3 letters = 114"
+        );
+    }
+
+    /// `to_board!()` as the game shows it with --qu
+    fn with_qu_tiles(
+        tiles: [[char; Board::WIDTH]; Board::HEIGHT],
+    ) -> [[Tile; Board::WIDTH]; Board::HEIGHT] {
+        tiles.map(|row| row.map(|c| Tile::from(c).as_qu()))
+    }
+
+    #[test]
+    /// From scoring.md: quo + nmmwelod = 108, played with a Qu tile. It's one tile worth 13 but
+    /// two letters of the word.
+    fn golden_qu_quo() {
+        let dict = Dictionary::from_words(["quo"], 3);
+        let tiles = to_board!("    o", "nmmqwelod");
+        assert!(Board::new_from(tiles, vec![], 3)
+            .find_words(&dict, usize::MAX)
+            .is_empty());
+
+        let b = Board::new_from(with_qu_tiles(tiles), vec![], 3);
+        let found = b.find_words(&dict, usize::MAX);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, to_path![2; (1, 3), (0, 4)]);
+        assert_eq!(found[0].score, 108);
+        assert_eq!(
+            b.explain_score("quo", &found[0].path).to_string(),
+            "quo + row clear nmmwelod = 36 x 3 letters = 108"
+        );
+    }
+
+    #[test]
+    /// From scoring.md: quote + alrrno nmmwelod = 245. Only 4 tiles with a Qu, but it's 5 letters
+    /// so it still blows up its neighbors.
+    fn golden_qu_quote() {
+        let b = Board::new_from(
+            with_qu_tiles(to_board!("  neo", "  rtr", "  aol", "nmmqwelod")),
+            vec![],
+            3,
+        );
+        let path = to_path![4; (3, 3), (2, 3), (1, 3), (0, 3)];
+        assert_eq!(b.score_for("quote", &path), 245);
+        assert_eq!(
+            b.explain_score("quote", &path).to_string(),
+            "quote + row clear nmmwelod + blast norral = 49 x 5 letters = 245"
+        );
+    }
+
+    #[test]
+    /// The same move as `golden_mux()`, under rules where `x` is worth more but doesn't clear
+    /// rows, and 3 letters is enough to blow up the neighbors
+    fn scoring_can_be_changed() {
+        let mut scoring =
+            Scoring::from_ron("(row_clearing: ['j', 'q', 'z'], blast_length: 3)").unwrap();
+        scoring.set_tile_value('x'.into(), 10);
+        let scoring: &'static Scoring = Box::leak(Box::new(scoring));
+
+        let b =
+            Board::new_from(to_board!("tqmuxbspi", "aaaaaaaaa"), vec![], 3).with_scoring(scoring);
+        let path = to_path![2; (0, 2), (0, 3), (0, 4)];
+        // m u x, the q and b either side, and the a below each of them
+        assert_eq!(b.score_for("mux", &path), (4 + 1 + 10 + 12 + 4 + 3) * 3);
+
+        let dict = Dictionary::from_words(["mux"], 3);
+        let found = b.find_words(&dict, usize::MAX);
+        assert_eq!(found[0].score, 102);
+        assert!(std::ptr::eq(
+            b.evolve_via(found[0].clone()).scoring,
+            scoring
+        ));
+    }
+
+    #[test]
+    /// Each multiplier used is worth the configured amount, added up: two 3x multipliers make 6x
+    fn multipliers_add_up() {
+        let scoring = Scoring::from_ron("(multiplier: 3)").unwrap();
+        let scoring: &'static Scoring = Box::leak(Box::new(scoring));
+
+        let mults = vec![(Board::HEIGHT - 1, 0), (Board::HEIGHT - 1, 2)];
+        let b = Board::new_from(to_board!("cat"), mults, 3).with_scoring(scoring);
+        let plain = Board::new_from(to_board!("cat"), vec![], 3).with_scoring(scoring);
+        let path = to_path![1; (0, 0), (0, 1), (0, 2)];
+        let unmultiplied = plain.score_for("cat", &path);
+        assert!(unmultiplied > 0);
+        assert_eq!(b.score_for("cat", &path), unmultiplied * 6);
+        assert_eq!(b.explain_score("cat", &path).multiplier, 6);
+
+        let one = Board::new_from(to_board!("cat"), vec![(Board::HEIGHT - 1, 1)], 3)
+            .with_scoring(scoring);
+        assert_eq!(one.score_for("cat", &path), unmultiplied * 3);
+    }
+
+    #[test]
+    /// Tiles can have any letter on, or a few letters at once. Words are spelled with whatever's
+    /// on the tiles, and their length is how many letters that comes to.
+    fn tiles_are_symbols() {
+        let scoring = Scoring::from_ron(
+            r#"(
+                letter_values: {'d': 3, 'i': 1, 'n': 2, 'o': 1, 's': 1, 'ñ': 8, "qu": 12},
+                row_clearing: ["qu"],
+            )"#,
+        )
+        .unwrap();
+        let scoring: &'static Scoring = Box::leak(Box::new(scoring));
+
+        let mut tiles = [[Tile::from(Board::EMPTY); Board::WIDTH]; Board::HEIGHT];
+        for (col, symbol) in ["n", "i", "ñ", "o", ".", "s", "qu", "i", "d"]
+            .into_iter()
+            .enumerate()
+        {
+            tiles[Board::HEIGHT - 1][col] = symbol.into();
+        }
+        let dict = Dictionary::from_words(["niño", "quid", "squid"], 3);
+
+        let b = Board::new_from(tiles, vec![], 3).with_scoring(scoring);
+        let found = b
+            .find_words(&dict, usize::MAX)
+            .into_iter()
+            .map(|w| (w.word, w.path.len(), w.score))
+            .collect::<Vec<_>>();
+        // The qu clears the row: 2 + 1 + 8 + 1 + 1 + 12 + 1 + 3 = 29
+        assert_eq!(
+            found,
+            [
+                ("niño".to_string(), 4, (2 + 1 + 8 + 1) * 4),
+                ("squid".to_string(), 4, 29 * 5),
+                ("quid".to_string(), 3, 29 * 4),
+            ]
+        );
+        assert_eq!(
+            b.explain_score("squid", &to_path![1; (0, 5), (0, 6), (0, 7), (0, 8)])
+                .to_string(),
+            "squid + 1 block + row clear niño = 29 x 5 letters = 145"
+        );
+
+        // squid is 5 letters, even if it's only 4 tiles
+        let b = Board::new_from(tiles, vec![], 5).with_scoring(scoring);
+        let found = b.find_words(&dict, usize::MAX);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].word, "squid");
+    }
+
+    #[test]
+    /// From scoring.md: auspice (u & i = 2x) + iodjsttkt = 280 * 4 = 1120. The `j` only gets
+    /// blown up for being next to the path, so it doesn't clear its row.
+    fn golden_auspice() {
+        let b = Board::new_from(
+            to_board!("     iod ", "    jaces", "    tuipt", "     kst "),
+            vec![(11, 5), (11, 6)],
+            3,
+        );
+        let path = to_path![4; (1, 5), (2, 5), (3, 6), (2, 7), (2, 6), (1, 6), (1, 7)];
+        assert_eq!(b.score_for("auspice", &path), 1120);
+    }
+
+    #[test]
+    /// From scoring.md: jealousies + 1 block, tadegtoneetollangircii o & last e are 2x = 580 x 4
+    /// = 2320. The `j` clears the bottom row, everything else is next to the path.
+    fn golden_jealousies() {
+        let b = Board::new_from(
+            to_board!(
+                "  .ta    ",
+                " deisegt ",
+                "osneeuolt",
+                " o   llaa",
+                "ngirciije"
+            ),
+            vec![(10, 6), (9, 2)],
+            3,
+        );
+        let path = to_path![5;
+            (4, 7),
+            (4, 8),
+            (3, 8),
+            (2, 7),
+            (2, 6),
+            (2, 5),
+            (1, 4),
+            (1, 3),
+            (1, 2),
+            (2, 1)
+        ];
+        assert_eq!(b.score_for("jealousies", &path), 2320);
+
+        let explained = b.explain_score("jealousies", &path);
+        assert_eq!(
+            (explained.letter_sum, explained.length, explained.multiplier),
+            (58, 10, 4)
+        );
+        assert_eq!(explained.multipliers, 2);
+        assert_eq!(explained.blocks, vec![Position::new(8, 2)]);
+        assert_eq!(
+            explained.to_string(),
+            "jealousies (2 multipliers) + 1 block + row clear ngircii + blast tadegtoneetolla = 58 x 10 letters x 4 = 2320"
+        );
+    }
+
     #[test]
     /// Test that board "evolution" and "gravity" work as expected. Given an
     /// input board and some `FoundWord`s to iterate over, make sure we get the
@@ -568,24 +1388,26 @@ mod board_tests {
             "eiusyijme"
         );
 
-        let output_board: Vec<Vec<String>> = to_board!(
+        let output_board = vec![
             "    bt   ",
             "   snepod",
             "   qsovaa",
             "  .sgsnnr",
-            "  usyijme"
-        );
+            "  usyijme",
+        ];
 
         let word_pickings = vec![
             FoundWord {
                 score: 1,
                 word: "ice".to_string(),
-                path: to_path![(1, 6), (1, 7), (1, 8)],
+                path: to_path![5; (1, 6), (1, 7), (1, 8)],
+                paths: 1,
             },
             FoundWord {
                 score: 1,
                 word: "icicle".to_string(),
-                path: to_path![(0, 0), (1, 1), (1, 2), (2, 1), (3, 0), (4, 0)],
+                path: to_path![5; (0, 0), (1, 1), (1, 2), (2, 1), (3, 0), (4, 0)],
+                paths: 1,
             },
         ];
 
@@ -595,37 +1417,307 @@ mod board_tests {
             b = b.evolve_via(findings);
         }
 
-        assert_eq!(b.tiles, output_board);
+        assert_eq!(bottom_rows(&b, 5), output_board);
+        assert_eq!(b.get_score(), 2);
     }
 
     #[test]
-    /// Test that a `Board`s `id` is based solely on the content of the tiles.
-    /// We do this by making two `Board`s that take the same tiles, but the
-    /// other input is different. We then verify that their `id`s are the same
-    /// while they're not pointing at the same object in memory.
-    ///
-    /// Note that if they're given the same parameters the compiler makes sure
-    /// they **do** point at the same object in memory!
+    /// Multipliers are stuck to their tiles, so they fall with them
+    fn multipliers_fall() {
+        let b = Board::new_from(to_board!("m", "c", "a", "t"), vec![(9, 0)], 3);
+        let b = b.evolve_via(FoundWord {
+            score: 1,
+            word: "cat".to_string(),
+            path: to_path![4; (1, 0), (2, 0), (3, 0)],
+            paths: 1,
+        });
+        assert_eq!(b.multipliers(), &vec![Position::new(12, 0)]);
+        assert_eq!(b.usable_tiles, 1);
+    }
+
+    #[test]
+    /// Test that a `Board`s `id` is based solely on the state of the game: the
+    /// tiles and the multipliers. Anything else we hand `new_from()` doesn't
+    /// matter.
     fn id_test() {
-        let sample_b1 = vec![
-            "i.ssbtpod".chars().map(|c| c.to_string()).collect(),
-            "mcisneice".chars().map(|c| c.to_string()).collect(),
-            "hcrqsovaa".chars().map(|c| c.to_string()).collect(),
-            "ln.sgsnnr".chars().map(|c| c.to_string()).collect(),
-            "eiusyijme".chars().map(|c| c.to_string()).collect(),
-        ];
-        let sample_b2 = vec![
-            "i.ssbtpod".chars().map(|c| c.to_string()).collect(),
-            "mcisneice".chars().map(|c| c.to_string()).collect(),
-            "hcrqsovaa".chars().map(|c| c.to_string()).collect(),
-            "ln.sgsnnr".chars().map(|c| c.to_string()).collect(),
-            "eiusyijme".chars().map(|c| c.to_string()).collect(),
-        ];
+        let sample = to_board!(
+            "i.ssbtpod",
+            "mcisneice",
+            "hcrqsovaa",
+            "ln.sgsnnr",
+            "eiusyijme"
+        );
 
-        let b1 = Board::new_from(sample_b1, vec![], 3);
-        let b2 = Board::new_from(sample_b2, vec![(0, 0), (1, 1), (2, 2)], 4);
+        let b1 = Board::new_from(sample, vec![(8, 0), (9, 1)], 3);
+        let b2 = Board::new_from(sample, vec![(9, 1), (8, 0)], 4);
+        let b3 = Board::new_from(sample, vec![(8, 0)], 3);
 
         assert_eq!(b1.id, b2.id);
+        assert_ne!(b1.id, b3.id);
+    }
+
+    #[test]
+    /// Board ids feed into tie-breaks, so they're pinned to a specific value. If this changes
+    /// then results from older runs are no longer comparable.
+    fn ids_are_stable() {
+        let mut tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+        tiles[Board::HEIGHT - 1] = ['s', 'p', 'e', 'l', 'l', '.', 't', 'o', 'w'];
+        tiles[Board::HEIGHT - 2][0] = 'e';
+
+        assert_eq!(Board::new_from(tiles, vec![], 3).id, 17492123268183956068);
+        assert_eq!(
+            Board::new_from(tiles, vec![(12, 1)], 3).id,
+            13994587815865316443
+        );
+    }
+}
+
+#[cfg(test)]
+mod gravity_props {
+    use super::*;
+    use proptest::prelude::*;
+
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+    /// A board that's already settled: every column is a stack of up to `stack` tiles sitting on
+    /// the bottom, made up of `letters` and blocks. Every so often a letter gets a multiplier.
+    pub(super) fn settled_board(
+        stack: usize,
+        letters: &'static [u8],
+    ) -> impl Strategy<Value = Board> {
+        let tile = prop_oneof![
+            8 => prop::sample::select(letters).prop_map(|c| c as char),
+            1 => Just(Board::BLOCK),
+        ];
+        let column = prop::collection::vec((tile, prop::bool::weighted(0.1)), 0..=stack);
+        prop::collection::vec(column, Board::WIDTH).prop_map(|columns| {
+            let mut tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+            let mut mults = vec![];
+            for (col, stack) in columns.iter().enumerate() {
+                for (height, (c, mult)) in stack.iter().enumerate() {
+                    let row = Board::HEIGHT - 1 - height;
+                    tiles[row][col] = *c;
+                    if *mult && *c != Board::BLOCK {
+                        mults.push((row, col));
+                    }
+                }
+            }
+            Board::new_from(tiles, mults, 3)
+        })
+    }
+
+    /// Walk a path over `board`'s letters, picking each step with the next of `choices`. Spells
+    /// out whatever it goes over, dictionary or not.
+    fn walk(board: &Board, choices: &[usize]) -> Option<FoundWord> {
+        let usable = board
+            .positions()
+            .filter(|p| board.is_usable(p))
+            .collect::<Vec<Position>>();
+        if usable.is_empty() {
+            return None;
+        }
+
+        let mut path = vec![usable[choices[0] % usable.len()].clone()];
+        for choice in &choices[1..] {
+            let next = board
+                .neighbors(path.last().unwrap())
+                .filter(|p| board.is_usable(p) && !path.contains(p))
+                .collect::<Vec<Position>>();
+            if next.is_empty() {
+                break;
+            }
+            path.push(next[choice % next.len()].clone());
+        }
+
+        let word = path
+            .iter()
+            .map(|p| board.get(p).symbol())
+            .collect::<String>();
+        Some(FoundWord {
+            score: board.score_for(&word, &path),
+            word,
+            path,
+            paths: 1,
+        })
+    }
+
+    fn tile_counts(board: &Board) -> HashMap<Tile, usize> {
+        let mut counts = HashMap::new();
+        for p in board.positions().filter(|p| board.get(p) != Board::EMPTY) {
+            *counts.entry(board.get(&p)).or_default() += 1;
+        }
+        counts
+    }
+
+    proptest! {
+        #[test]
+        fn destruction_and_gravity_hold_up(
+            board in settled_board(Board::HEIGHT, ALPHABET),
+            choices in prop::collection::vec(any::<usize>(), 1..10),
+        ) {
+            let Some(found_word) = walk(&board, &choices) else {
+                return Ok(());
+            };
+            let destroyed = board.destruction_mask(&found_word.path);
+            let next = board.evolve_via(found_word.clone());
+
+            // The explanation adds up to the same score, and accounts for every destroyed tile
+            let explained = board.explain_score(&found_word.word, &found_word.path);
+            prop_assert_eq!(explained.score, found_word.score);
+            let accounted = explained.path.len()
+                + explained.row_cleared.len()
+                + explained.blasted.len()
+                + explained.blocks.len();
+            let letters_and_blocks = bitboard::cells(destroyed)
+                .filter(|idx| board.tile(*idx) != Board::EMPTY)
+                .count();
+            prop_assert_eq!(accounted, letters_and_blocks);
+
+            // Everything that was there is still there, apart from what got blown up
+            let mut expected = tile_counts(&board);
+            for idx in bitboard::cells(destroyed) {
+                let c = board.tile(idx);
+                if c != Board::EMPTY {
+                    *expected.get_mut(&c).unwrap() -= 1;
+                }
+            }
+            expected.retain(|_, count| *count > 0);
+            prop_assert_eq!(tile_counts(&next), expected);
+
+            // Nothing is left floating
+            for col in 0..Board::WIDTH {
+                let first_tile = (0..Board::HEIGHT)
+                    .find(|row| next.get(&Position::new(*row, col)) != Board::EMPTY)
+                    .unwrap_or(Board::HEIGHT);
+                for row in first_tile..Board::HEIGHT {
+                    prop_assert_ne!(next.get(&Position::new(row, col)), Board::EMPTY);
+                }
+            }
+
+            // Blocks only go if they're right next to the path, or on a row it cleared
+            let cleared_rows = found_word
+                .word
+                .chars()
+                .zip(&found_word.path)
+                .filter(|(c, _)| "jqxz".contains(*c))
+                .map(|(_, p)| p.row)
+                .collect::<Vec<u8>>();
+            for idx in bitboard::cells(destroyed) {
+                let p = bitboard::position_of(idx);
+                if board.get(&p) == Board::BLOCK {
+                    let next_to_path = p
+                        .cardinal_neighbors(board.width(), board.height())
+                        .iter()
+                        .any(|n| found_word.path.contains(n));
+                    prop_assert!(next_to_path || cleared_rows.contains(&p.row));
+                }
+            }
+
+            // Multipliers only go when their tile does, and otherwise stick to a letter
+            let lost_mults = board
+                .multipliers()
+                .iter()
+                .filter(|m| destroyed & bitboard::bit(bitboard::index_of(m)) != 0)
+                .count();
+            prop_assert_eq!(next.multipliers().len(), board.multipliers().len() - lost_mults);
+            for m in next.multipliers() {
+                prop_assert!(next.is_usable(m));
+            }
+
+            let recount = next.positions().filter(|p| next.is_usable(p)).count();
+            prop_assert_eq!(next.usable_tiles, recount);
+        }
+    }
+}
+
+#[cfg(test)]
+mod reference_search {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// The slow, obvious way to find words: walk every path that doesn't cross itself, up to as
+    /// long as the longest word, and look each one up. No prefix pruning, no bitmasks, no tables.
+    fn every_word(board: &Board, dict: &Dictionary) -> Vec<FoundWord> {
+        fn walk(
+            board: &Board,
+            dict: &Dictionary,
+            path: &mut Vec<Position>,
+            found_words: &mut Vec<FoundWord>,
+        ) {
+            let word = path
+                .iter()
+                .map(|p| board.get(p).symbol())
+                .collect::<String>();
+            if board.is_long_enough(&word) && dict.is_word(&word) {
+                found_words.push(FoundWord {
+                    score: board.score_for(&word, path),
+                    word,
+                    path: path.clone(),
+                    paths: 1,
+                });
+            }
+            if path.len() == dict.longest_word() {
+                return;
+            }
+
+            let last = path.last().unwrap().clone();
+            for next in last.neighbors(board.width(), board.height()).iter() {
+                if board.is_usable(next) && !path.contains(next) {
+                    path.push(next.clone());
+                    walk(board, dict, path, found_words);
+                    path.pop();
+                }
+            }
+        }
+
+        let mut found_words = vec![];
+        for start in board.positions().filter(|p| board.is_usable(p)) {
+            walk(board, dict, &mut vec![start], &mut found_words);
+        }
+        found_words
+    }
+
+    fn sorted(mut found_words: Vec<FoundWord>) -> Vec<(String, Vec<Position>, u32)> {
+        found_words.sort_by(|a, b| a.path.cmp(&b.path));
+        found_words
+            .into_iter()
+            .map(|f| (f.word, f.path, f.score))
+            .collect()
+    }
+
+    /// Few enough letters that most boards are full of words, plus a couple that clear rows
+    const LETTERS: &[u8] = b"abeiostz";
+
+    fn words() -> impl Strategy<Value = Vec<String>> {
+        let word = prop::collection::vec(prop::sample::select(LETTERS), 1..=5)
+            .prop_map(|w| String::from_utf8(w).unwrap());
+        prop::collection::vec(word, 1..40)
+    }
+
+    #[test]
+    fn agrees_on_the_sample_board() {
+        let mut tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+        tiles[Board::HEIGHT - 2][..5].copy_from_slice(&['s', 't', 'a', 'b', Board::BLOCK]);
+        tiles[Board::HEIGHT - 1][..5].copy_from_slice(&['e', 'a', 's', 't', 'z']);
+        let board = Board::new_from(tiles, vec![(Board::HEIGHT - 1, 1)], 3);
+        let dict = Dictionary::from_words(["sea", "eat", "east", "seat", "stab", "tabs", "at"], 3);
+
+        let expected = sorted(every_word(&board, &dict));
+        assert!(expected.len() > 5);
+        assert_eq!(sorted(board.find_words(&dict, usize::MAX)), expected);
+    }
+
+    proptest! {
+        #[test]
+        fn find_words_matches_reference(
+            board in gravity_props::settled_board(3, LETTERS),
+            words in words(),
+        ) {
+            let dict = Dictionary::from_words(words, 1);
+            prop_assert_eq!(
+                sorted(board.find_words(&dict, usize::MAX)),
+                sorted(every_word(&board, &dict))
+            );
+        }
     }
 }
-    */
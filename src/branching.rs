@@ -0,0 +1,138 @@
+use crate::board::Board;
+use crate::Args;
+
+/// Works out how many children (the `top_n` given to `Board::find_words`) each board gets, rather
+/// than everything getting the same -c.
+///
+/// There are three knobs, all of which can be combined:
+///   - a schedule: generation 1 gets the first value, generation 2 the second, and so on, with the
+///     last value sticking around for every generation after that
+///   - a budget: the most new boards a generation should spawn, shared out evenly between the
+///     boards in it
+///   - a multiplier bonus: extra children for each multiplier still on a board, since that's where
+///     the big points are
+pub struct ChildLimits {
+    schedule: Vec<usize>,
+    budget: Option<usize>,
+    multiplier_bonus: usize,
+}
+
+impl ChildLimits {
+    pub fn new(schedule: Vec<usize>, budget: Option<usize>, multiplier_bonus: usize) -> Self {
+        assert!(!schedule.is_empty(), "Need at least one child limit");
+        ChildLimits {
+            schedule,
+            budget,
+            multiplier_bonus,
+        }
+    }
+
+    pub fn from_args(args: &Args) -> Self {
+        let schedule = if args.children_schedule.is_empty() {
+            vec![args.max_children]
+        } else {
+            args.children_schedule.clone()
+        };
+        ChildLimits::new(schedule, args.gen_board_budget, args.multiplier_bonus)
+    }
+
+    /// The limits for boards in `generation` (starting at 1), when there are `boards` of them
+    pub fn for_generation(&self, generation: u32, boards: usize) -> GenerationLimit {
+        let idx = std::cmp::min(generation as usize, self.schedule.len()) - 1;
+        GenerationLimit {
+            scheduled: self.schedule[idx],
+            share: self
+                .budget
+                .map(|budget| std::cmp::max(budget / std::cmp::max(boards, 1), 1)),
+        }
+    }
+
+    /// The limit for `board` in a generation limited by `limit`. The multiplier bonus goes on
+    /// before the budget's share is taken off, so the budget is never overspent.
+    pub fn for_board(&self, limit: GenerationLimit, board: &Board) -> usize {
+        let wanted = limit
+            .scheduled
+            .saturating_add(self.multiplier_bonus * board.multipliers().len());
+        match limit.share {
+            Some(share) => std::cmp::min(wanted, share),
+            None => wanted,
+        }
+    }
+}
+
+/// What `ChildLimits::for_generation` works out once per generation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GenerationLimit {
+    /// The schedule's (or -c's) value for the generation
+    pub scheduled: usize,
+    /// Each board's share of the budget, if there is one
+    pub share: Option<usize>,
+}
+
+#[cfg(test)]
+mod branching_tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn schedule_sticks_on_the_last_value() {
+        let limits = ChildLimits::new(vec![10, 5, 3], None, 0);
+        assert_eq!(limits.for_generation(1, 1).scheduled, 10);
+        assert_eq!(limits.for_generation(2, 1_000).scheduled, 5);
+        assert_eq!(limits.for_generation(3, 1).scheduled, 3);
+        assert_eq!(limits.for_generation(30, 1).scheduled, 3);
+    }
+
+    #[test]
+    /// A budget only ever narrows things, and everyone gets at least one child
+    fn budget_is_shared_out() {
+        let board = Board::new_from([[Board::EMPTY; Board::WIDTH]; Board::HEIGHT], vec![], 3);
+        let limits = ChildLimits::new(vec![10], Some(1_000), 0);
+        assert_eq!(limits.for_board(limits.for_generation(1, 1), &board), 10);
+        assert_eq!(limits.for_board(limits.for_generation(2, 250), &board), 4);
+        assert_eq!(
+            limits.for_board(limits.for_generation(3, 1_000_000), &board),
+            1
+        );
+    }
+
+    #[test]
+    fn multipliers_widen_boards() {
+        let tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+        let limits = ChildLimits::new(vec![5], None, 3);
+        let limit = limits.for_generation(1, 1);
+        assert_eq!(
+            limits.for_board(limit, &Board::new_from(tiles, vec![], 3)),
+            5
+        );
+        assert_eq!(
+            limits.for_board(limit, &Board::new_from(tiles, vec![(0, 0), (1, 1)], 3)),
+            11
+        );
+    }
+
+    #[test]
+    /// The multiplier bonus can't push a board past its share of the budget
+    fn budget_caps_the_bonus() {
+        let tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+        let board = Board::new_from(tiles, vec![(0, 0), (1, 1)], 3);
+        let limits = ChildLimits::new(vec![5], Some(100), 3);
+        assert_eq!(limits.for_board(limits.for_generation(1, 10), &board), 10);
+        assert_eq!(limits.for_board(limits.for_generation(1, 50), &board), 2);
+    }
+
+    #[test]
+    /// A schedule would silently win over these, so they can't be given together
+    fn schedule_conflicts_with_other_limits() {
+        let parse = |cli: &str| Args::try_parse_from(cli.split_whitespace());
+        assert!(
+            parse("spell-tower-solver sample-input/board-1.ron --children-schedule 10,5").is_ok()
+        );
+        for other in ["-c 3", "-s 2", "--sweep-children 1,2"] {
+            let cli = format!(
+                "spell-tower-solver sample-input/board-1.ron --children-schedule 10,5 {other}"
+            );
+            assert!(parse(&cli).is_err(), "{other} was allowed");
+        }
+    }
+}
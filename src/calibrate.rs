@@ -0,0 +1,482 @@
+//! Checking our scoring against scores seen in real games, and working out what the scoring rules
+//! would have to be to match them. This is what `scoring.md` and `scoring.py` did by hand.
+
+use crate::board::Board;
+use crate::position::Position;
+use crate::scoring::Scoring;
+use crate::tile::Tile;
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// One move seen in a real game, and what it scored
+#[derive(Debug, Deserialize)]
+pub struct Observation {
+    /// The bottom rows of the board, top row first, one letter per tile. Only needs to cover the
+    /// move and whatever it could blow up.
+    pub board: Vec<String>,
+    /// Multipliers, as (row, col) within `board`
+    #[serde(default)]
+    pub mults: Vec<(usize, usize)>,
+    /// The game was showing Qu tiles (see --qu), so every `q` in `board` is a tile spelling qu
+    #[serde(default)]
+    pub qu: bool,
+    /// The move, as (row, col) within `board`
+    pub path: Vec<(usize, usize)>,
+    pub score: u32,
+}
+
+impl Observation {
+    /// How far down the real board `board`'s first row is
+    fn first_row(&self) -> usize {
+        Board::HEIGHT - self.board.len()
+    }
+
+    /// The board, scoring with `scoring`. Any word length goes, it was played after all.
+    pub fn to_board(&self, scoring: &'static Scoring) -> Board {
+        let mut tiles = [[Tile::from(Board::EMPTY); Board::WIDTH]; Board::HEIGHT];
+        for (r, row) in self.board.iter().enumerate() {
+            for (col, c) in row.chars().enumerate() {
+                let tile = Tile::from(c);
+                tiles[self.first_row() + r][col] = if self.qu { tile.as_qu() } else { tile };
+            }
+        }
+        let mults = self
+            .mults
+            .iter()
+            .map(|(row, col)| (self.first_row() + row, *col))
+            .collect();
+        Board::new_from(tiles, mults, 1).with_scoring(scoring)
+    }
+
+    pub fn to_path(&self) -> Vec<Position> {
+        self.path
+            .iter()
+            .map(|(row, col)| Position::new(self.first_row() + row, *col))
+            .collect()
+    }
+
+    /// What `path` spells out on `board`
+    pub fn word(&self, board: &Board) -> String {
+        self.to_path()
+            .iter()
+            .map(|p| board.get(p).symbol())
+            .collect()
+    }
+
+    /// Everything about the move that scoring cares about, going by what the board destroys when
+    /// it's scoring with `scoring`
+    fn features(&self, scoring: &'static Scoring) -> Features {
+        let board = self.to_board(scoring);
+        let path = self.to_path();
+        let explained = board.explain_score(&self.word(&board), &path);
+
+        let mut counts = BTreeMap::new();
+        for (_, tile) in explained
+            .path
+            .iter()
+            .chain(&explained.row_cleared)
+            .chain(&explained.blasted)
+        {
+            *counts.entry(*tile).or_default() += 1;
+        }
+        Features {
+            counts,
+            length: explained.length,
+            multipliers: explained.multipliers,
+            score: self.score,
+        }
+    }
+
+    /// Whether every letter, multiplier and step of the move fits on the board
+    fn fits_on_board(&self) -> bool {
+        let letters_ok = self
+            .board
+            .iter()
+            .flat_map(|row| row.chars())
+            .all(|c| c.is_lowercase() || c == Board::BLOCK || c == Board::EMPTY);
+        let widths_ok = self
+            .board
+            .iter()
+            .all(|row| row.chars().count() <= Board::WIDTH);
+        let on_board = |(row, col): &(usize, usize)| *row < self.board.len() && *col < Board::WIDTH;
+        letters_ok
+            && widths_ok
+            && self.board.len() <= Board::HEIGHT
+            && self.path.iter().all(on_board)
+            && self.mults.iter().all(on_board)
+    }
+}
+
+/// An observation boiled down to what scoring needs: how many of every tile it destroys, how
+/// many letters long it is and how many multipliers it uses
+struct Features {
+    counts: BTreeMap<Tile, u32>,
+    length: u32,
+    multipliers: u32,
+    /// What it actually scored
+    score: u32,
+}
+
+impl Features {
+    /// What the letter sum gets multiplied by: the word's length, and any multipliers
+    fn factor(&self, scoring: &Scoring) -> u32 {
+        self.length * std::cmp::max(scoring.multiplier * self.multipliers, 1)
+    }
+
+    fn score(&self, scoring: &Scoring) -> u32 {
+        let letter_sum = self
+            .counts
+            .iter()
+            .map(|(tile, count)| count * scoring.tile_value(*tile))
+            .sum::<u32>();
+        letter_sum * self.factor(scoring)
+    }
+}
+
+/// Read a file of observations, see `sample-input/observations.ron`
+pub fn load(path: &str) -> Vec<Observation> {
+    let contents = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Couldn't read observations from {}: {}", path, e));
+    let observations: Vec<Observation> = ron::from_str(&contents)
+        .unwrap_or_else(|e| panic!("{} doesn't look like a list of observations: {}", path, e));
+
+    for (i, observation) in observations.iter().enumerate() {
+        assert!(
+            observation.fits_on_board(),
+            "Observation {} in {} has a board or move we can't use",
+            i + 1,
+            path
+        );
+    }
+    observations
+}
+
+/// How well some scoring fits the observations
+#[derive(Debug)]
+pub struct Fit {
+    pub scoring: Scoring,
+    /// Indexes of the observations `scoring` gets wrong
+    pub mismatches: Vec<usize>,
+    pub squared_error: u64,
+}
+
+impl Fit {
+    fn of(scoring: Scoring, observations: &[Features]) -> Fit {
+        let mut mismatches = vec![];
+        let mut squared_error = 0;
+        for (i, observation) in observations.iter().enumerate() {
+            let diff = observation.score(&scoring).abs_diff(observation.score) as u64;
+            if diff != 0 {
+                mismatches.push(i);
+                squared_error += diff * diff;
+            }
+        }
+        Fit {
+            scoring,
+            mismatches,
+            squared_error,
+        }
+    }
+}
+
+/// Find the scoring that best explains `observations`, starting from `start`. Which letters clear
+/// rows stays as it is in `start`.
+///
+/// For each blast length and multiplier we try, the letter values come from a least squares fit:
+/// every observation's score is its letter counts times the letter values, times its length and
+/// multipliers, so it's linear in the letter values. A little pull back towards `start` keeps
+/// letters the observations say nothing about where they were. Fewest mismatches wins, then
+/// smallest error, then whatever's closest to `start`.
+pub fn fit(observations: &[Observation], start: &Scoring) -> Fit {
+    let mut fits = vec![];
+    for blast_length in 3..=8 {
+        let mut blasting = start.clone();
+        blasting.blast_length = blast_length;
+        let blasting: &'static Scoring = Box::leak(Box::new(blasting));
+        let features = observations
+            .iter()
+            .map(|o| o.features(blasting))
+            .collect::<Vec<Features>>();
+        for multiplier in 2..=4 {
+            let mut scoring = start.clone();
+            scoring.blast_length = blast_length;
+            scoring.multiplier = multiplier;
+            fit_letters(&features, &mut scoring);
+            fits.push(polish(scoring, &features));
+        }
+    }
+
+    let distance = |scoring: &Scoring| {
+        scoring.blast_length.abs_diff(start.blast_length)
+            + scoring.multiplier.abs_diff(start.multiplier) as usize
+    };
+    fits.into_iter()
+        .min_by_key(|fit| {
+            (
+                fit.mismatches.len(),
+                fit.squared_error,
+                distance(&fit.scoring),
+            )
+        })
+        .unwrap()
+}
+
+/// Every tile that turns up in `observations`, in order
+fn letters_in(observations: &[Features]) -> Vec<Tile> {
+    observations
+        .iter()
+        .flat_map(|o| o.counts.keys().copied())
+        .collect::<BTreeSet<Tile>>()
+        .into_iter()
+        .collect()
+}
+
+/// Nudge letter values a point or two at a time for as long as that gets more observations
+/// right (or the same ones closer). Least squares spreads the blame for a bad observation across
+/// every letter in it, this puts it back on the observation.
+fn polish(scoring: Scoring, observations: &[Features]) -> Fit {
+    let score = |fit: &Fit| (fit.mismatches.len(), fit.squared_error);
+    let letters = letters_in(observations);
+
+    let mut best = Fit::of(scoring, observations);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for letter in &letters {
+            for nudge in [-2, -1, 1, 2] {
+                let value = best.scoring.tile_value(*letter);
+                let Some(value) = value.checked_add_signed(nudge) else {
+                    continue;
+                };
+                let mut scoring = best.scoring.clone();
+                scoring.set_tile_value(*letter, value);
+                let fit = Fit::of(scoring, observations);
+                if score(&fit) < score(&best) {
+                    best = fit;
+                    improved = true;
+                }
+            }
+        }
+    }
+    best
+}
+
+/// Least squares values, rounded to whole points, for every letter in `observations`
+fn fit_letters(observations: &[Features], scoring: &mut Scoring) {
+    const PULL: f64 = 1e-3;
+    let letters = letters_in(observations);
+    let n = letters.len();
+
+    // The normal equations, (AᵀA + PULL·I) v = Aᵀy + PULL·start
+    let mut lhs = vec![vec![0.0; n]; n];
+    let mut rhs = vec![0.0; n];
+    for (i, letter) in letters.iter().enumerate() {
+        lhs[i][i] = PULL;
+        rhs[i] = PULL * scoring.tile_value(*letter) as f64;
+    }
+    for observation in observations {
+        let factor = observation.factor(scoring) as f64;
+        let a = letters
+            .iter()
+            .map(|c| *observation.counts.get(c).unwrap_or(&0) as f64 * factor)
+            .collect::<Vec<f64>>();
+        for i in 0..n {
+            rhs[i] += a[i] * observation.score as f64;
+            for j in 0..n {
+                lhs[i][j] += a[i] * a[j];
+            }
+        }
+    }
+
+    for (letter, value) in letters.iter().zip(solve(lhs, rhs)) {
+        scoring.set_tile_value(*letter, value.round().max(0.0) as u32);
+    }
+}
+
+/// Gaussian elimination with partial pivoting. The pull in `fit_letters()` means there's always
+/// an answer.
+#[allow(clippy::needless_range_loop)]
+fn solve(mut lhs: Vec<Vec<f64>>, mut rhs: Vec<f64>) -> Vec<f64> {
+    let n = rhs.len();
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|a, b| lhs[*a][col].abs().total_cmp(&lhs[*b][col].abs()))
+            .unwrap();
+        lhs.swap(col, pivot);
+        rhs.swap(col, pivot);
+
+        for row in col + 1..n {
+            let ratio = lhs[row][col] / lhs[col][col];
+            for k in col..n {
+                lhs[row][k] -= ratio * lhs[col][k];
+            }
+            rhs[row] -= ratio * rhs[col];
+        }
+    }
+
+    let mut v = vec![0.0; n];
+    for row in (0..n).rev() {
+        let rest = (row + 1..n).map(|k| lhs[row][k] * v[k]).sum::<f64>();
+        v[row] = (rhs[row] - rest) / lhs[row][row];
+    }
+    v
+}
+
+/// Check `scoring` against every observation in `path`, and if `refit`, work out the scoring
+/// that fits them best
+pub fn run(path: &str, scoring: &'static Scoring, refit: bool) {
+    let observations = load(path);
+    println!("Checking {} observations from {}", observations.len(), path);
+
+    let mut matched = 0;
+    for observation in &observations {
+        let board = observation.to_board(scoring);
+        let word = observation.word(&board);
+        let explained = board.explain_score(&word, &observation.to_path());
+        if explained.score == observation.score {
+            matched += 1;
+        } else {
+            println!("  saw {}, expected {}", observation.score, explained);
+        }
+    }
+    println!("{} of {} match", matched, observations.len());
+
+    if !refit {
+        return;
+    }
+
+    let best = fit(&observations, scoring);
+    println!();
+    println!(
+        "Best fit: words of {}+ letters blast, multipliers are worth {}x, {} of {} match",
+        best.scoring.blast_length,
+        best.scoring.multiplier,
+        observations.len() - best.mismatches.len(),
+        observations.len()
+    );
+    let letters = scoring
+        .letter_values()
+        .chain(best.scoring.letter_values())
+        .map(|(letter, _)| letter)
+        .collect::<BTreeSet<Tile>>();
+    for letter in letters {
+        let (was, now) = (scoring.tile_value(letter), best.scoring.tile_value(letter));
+        if was != now {
+            println!("  {}: {} -> {}", letter, was, now);
+        }
+    }
+    let best_scoring: &'static Scoring = Box::leak(Box::new(best.scoring.clone()));
+    for i in &best.mismatches {
+        let observation = &observations[*i];
+        let features = observation.features(best_scoring);
+        println!(
+            "  still off: {} saw {}, fit gives {}",
+            observation.word(&observation.to_board(scoring)),
+            observation.score,
+            features.score(&best.scoring)
+        );
+    }
+
+    if best.scoring != *scoring {
+        println!();
+        println!("As a scoring file, for --scoring:");
+        println!("{}", best.scoring.to_ron());
+    }
+}
+
+#[cfg(test)]
+mod calibrate_tests {
+    use super::*;
+
+    fn sample() -> Vec<Observation> {
+        ron::from_str(include_str!("../sample-input/observations.ron")).unwrap()
+    }
+
+    fn word_of(observation: &Observation) -> String {
+        observation.word(&observation.to_board(Scoring::standard()))
+    }
+
+    #[test]
+    /// Everything but "nix" agrees with `score_for()`, and so does our own take on the rules
+    fn sample_matches_score_for() {
+        let scoring = Scoring::standard();
+        for observation in sample() {
+            let board = observation.to_board(scoring);
+            let word = observation.word(&board);
+            let score = board.score_for(&word, &observation.to_path());
+            let features = observation.features(scoring);
+            assert_eq!(features.score(scoring), score, "{}", word);
+            if word != "nix" {
+                assert_eq!(score, observation.score, "{}", word);
+            }
+        }
+    }
+
+    #[test]
+    /// Rows that are too wide, and moves or multipliers off the board, get caught before they
+    /// can index past the edge of it
+    fn off_board_observations_dont_fit() {
+        assert!(sample().iter().all(|o| o.fits_on_board()));
+        for bad in [
+            r#"(board: ["abcdefghij"], path: [(0, 0)], score: 1)"#,
+            r#"(board: ["cat"], path: [(0, 0), (1, 0)], score: 1)"#,
+            r#"(board: ["cat"], path: [(0, 0), (0, 9)], score: 1)"#,
+            r#"(board: ["cat"], mults: [(2, 0)], path: [(0, 0)], score: 1)"#,
+        ] {
+            let observation: Observation = ron::from_str(bad).unwrap();
+            assert!(!observation.fits_on_board(), "{}", bad);
+        }
+    }
+
+    #[test]
+    /// "nix" can't be fit without breaking "six" and "tix", so it stays a mismatch and nothing
+    /// else changes
+    fn fit_keeps_the_standard_scoring() {
+        let observations = sample();
+        let best = fit(&observations, Scoring::standard());
+        assert_eq!(&best.scoring, Scoring::standard());
+        let off = best
+            .mismatches
+            .iter()
+            .map(|i| word_of(&observations[*i]))
+            .collect::<Vec<String>>();
+        assert_eq!(off, vec!["nix"]);
+    }
+
+    #[test]
+    /// Starting from every letter being worth 1, and the wrong rules, the fit gets back to the
+    /// standard scoring for every letter the observations use
+    fn fit_recovers_the_standard_scoring() {
+        let observations = sample()
+            .into_iter()
+            .filter(|o| word_of(o) != "nix")
+            .collect::<Vec<Observation>>();
+        let mut start = Scoring::standard().clone();
+        for (letter, _) in Scoring::standard().letter_values() {
+            start.set_tile_value(letter, 1);
+        }
+        start.blast_length = 7;
+        start.multiplier = 3;
+
+        let best = fit(&observations, &start);
+        assert!(best.mismatches.is_empty());
+        assert_eq!(best.scoring.blast_length, Scoring::standard().blast_length);
+        assert_eq!(best.scoring.multiplier, Scoring::standard().multiplier);
+
+        let seen = observations
+            .iter()
+            .flat_map(|o| o.board.iter().flat_map(|row| row.chars()))
+            .filter(|c| c.is_lowercase())
+            .map(Tile::from)
+            .collect::<BTreeSet<Tile>>();
+        for c in seen {
+            assert_eq!(
+                best.scoring.tile_value(c),
+                Scoring::standard().tile_value(c),
+                "{}",
+                c
+            );
+        }
+    }
+}
@@ -7,6 +7,7 @@ use std::io::{BufRead, BufReader};
 pub struct Dictionary {
     word_cache: HashSet<String>,
     path_cache: HashSet<String>,
+    longest_word: usize,
 }
 
 impl Dictionary {
@@ -14,6 +15,7 @@ impl Dictionary {
         let mut d = Dictionary {
             word_cache: HashSet::new(),
             path_cache: HashSet::new(),
+            longest_word: 0,
         };
 
         if !args.quiet {
@@ -29,16 +31,14 @@ impl Dictionary {
             }))
             .lines()
             .map(|l| l.unwrap())
-            .filter(|w| w.len() >= args.min_word_length)
+            .filter(|w| w.chars().count() >= args.min_word_length)
             .collect::<Vec<String>>();
 
-        let bar: ProgressBar;
-
-        if args.quiet {
-            bar = ProgressBar::hidden();
+        let bar = if args.quiet {
+            ProgressBar::hidden()
         } else {
-            bar = ProgressBar::new(words.len() as u64);
-        }
+            ProgressBar::new(words.len() as u64)
+        };
 
         bar.set_style(
             ProgressStyle::with_template(
@@ -50,13 +50,8 @@ impl Dictionary {
 
         bar.set_message("Populating caches");
 
-        words.iter().for_each(|word|{
-            for l in 2..=word.len() {
-                let mut prefix = String::with_capacity(l);
-                prefix.push_str(&word[0..l]);
-                d.path_cache.insert(prefix);
-            }
-            d.word_cache.insert(word.clone());
+        words.iter().for_each(|word| {
+            d.add_word(word);
             bar.inc(1);
         });
         bar.finish();
@@ -64,6 +59,45 @@ impl Dictionary {
         d
     }
 
+    /// Build a dictionary straight from a list of words rather than a file
+    pub fn from_words<I, S>(words: I, min_word_length: usize) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut d = Dictionary {
+            word_cache: HashSet::new(),
+            path_cache: HashSet::new(),
+            longest_word: 0,
+        };
+
+        words
+            .into_iter()
+            .filter(|w| w.as_ref().chars().count() >= min_word_length)
+            .for_each(|w| d.add_word(w.as_ref()));
+
+        d
+    }
+
+    fn add_word(&mut self, word: &str) {
+        // Prefixes of 2 or more letters, cut on letter boundaries rather than bytes
+        let ends = word
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(word.len()))
+            .skip(2);
+        for end in ends {
+            self.path_cache.insert(word[..end].to_string());
+        }
+        self.word_cache.insert(word.to_string());
+        self.longest_word = std::cmp::max(self.longest_word, word.chars().count());
+    }
+
+    /// Length of the longest word we know about, in letters
+    pub fn longest_word(&self) -> usize {
+        self.longest_word
+    }
+
     pub fn has_path(&self, prefix: &str) -> bool {
         let has = self.path_cache.get(prefix);
         has.is_some()
@@ -74,3 +108,22 @@ impl Dictionary {
         has.is_some()
     }
 }
+
+#[cfg(test)]
+mod dictionary_tests {
+    use super::*;
+
+    #[test]
+    /// Lengths are counted in letters, so accents don't make a word any longer
+    fn lengths_are_letters() {
+        let dict = Dictionary::from_words(["café", "cafe", "né", "été"], 3);
+        assert!(dict.is_word("café"));
+        assert!(dict.is_word("été"));
+        assert!(!dict.is_word("né"));
+        assert_eq!(dict.longest_word(), 4);
+        assert!(dict.has_path("ca"));
+        assert!(dict.has_path("caf"));
+        assert!(dict.has_path("café"));
+        assert!(dict.has_path("ét"));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::board::Board;
+use crate::dictionary::Dictionary;
+
+use clap::ValueEnum;
+
+/// Something that can look at a board and guess how many more points are left in it. When there
+/// are too many boards in a generation this is what decides which ones we keep.
+pub trait BoardEvaluator: Sync {
+    /// How many points we think are still to be had from `board`, on top of what it's already
+    /// scored. Bigger is better.
+    fn evaluate(&self, board: &Board, dict: &Dictionary) -> u32;
+}
+
+/// Which `BoardEvaluator` to rank boards with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Evaluator {
+    /// Don't guess, just go with the score so far
+    None,
+    /// Add up the scores of the best few words on the board
+    TopWords,
+    /// Add up the high value letters that can still be used
+    Letters,
+    /// Give credit for every multiplier that's still around
+    Multipliers,
+}
+
+impl Evaluator {
+    pub fn build(&self, top_k: usize) -> Box<dyn BoardEvaluator> {
+        match self {
+            Evaluator::None => Box::new(NoEvaluation),
+            Evaluator::TopWords => Box::new(TopWords { k: top_k }),
+            Evaluator::Letters => Box::new(HighValueLetters {
+                min_value: HighValueLetters::DEFAULT_MIN_VALUE,
+            }),
+            Evaluator::Multipliers => Box::new(LiveMultipliers),
+        }
+    }
+}
+
+pub struct NoEvaluation;
+
+impl BoardEvaluator for NoEvaluation {
+    fn evaluate(&self, _board: &Board, _dict: &Dictionary) -> u32 {
+        0
+    }
+}
+
+/// The sum of the `k` best scoring words we could play next. This means actually searching the
+/// board, so it's by far the most expensive of these.
+pub struct TopWords {
+    pub k: usize,
+}
+
+impl BoardEvaluator for TopWords {
+    fn evaluate(&self, board: &Board, dict: &Dictionary) -> u32 {
+        let mut words = board.find_words(dict, usize::MAX);
+        words.sort_by_key(|w| std::cmp::Reverse(w.score));
+        words.iter().take(self.k).map(|w| w.score).sum()
+    }
+}
+
+/// The value of every letter worth at least `min_value` that has a usable neighbor (so could
+/// still end up in a word), multiplied by the shortest word it could be a part of.
+pub struct HighValueLetters {
+    pub min_value: u32,
+}
+
+impl HighValueLetters {
+    pub const DEFAULT_MIN_VALUE: u32 = 4;
+}
+
+impl BoardEvaluator for HighValueLetters {
+    fn evaluate(&self, board: &Board, _dict: &Dictionary) -> u32 {
+        board
+            .positions()
+            .filter(|p| board.is_usable(p))
+            .filter(|p| board.neighbors(p).any(|n| board.is_usable(&n)))
+            .map(|p| board.tile_score(board.get(&p)))
+            .filter(|v| *v >= self.min_value)
+            .sum::<u32>()
+            * board.min_word_length() as u32
+    }
+}
+
+/// Every multiplier still on the board will at least double a shortest word going through it
+pub struct LiveMultipliers;
+
+impl BoardEvaluator for LiveMultipliers {
+    fn evaluate(&self, board: &Board, _dict: &Dictionary) -> u32 {
+        board
+            .multipliers()
+            .iter()
+            .map(|p| board.tile_score(board.get(p)) * board.min_word_length() as u32)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod evaluator_tests {
+    use super::*;
+
+    fn board_with(rows: &[&str], mults: Vec<(usize, usize)>) -> Board {
+        let mut tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+        let first_row = Board::HEIGHT - rows.len();
+        for (r, row) in rows.iter().enumerate() {
+            for (col, c) in row.chars().enumerate() {
+                tiles[first_row + r][col] = c;
+            }
+        }
+        Board::new_from(tiles, mults, 3)
+    }
+
+    #[test]
+    fn nothing_means_nothing() {
+        let dict = Dictionary::from_words(["cat"], 3);
+        let b = board_with(&["cat"], vec![(12, 0)]);
+        assert_eq!(Evaluator::None.build(3).evaluate(&b, &dict), 0);
+    }
+
+    #[test]
+    /// "cat" and "tac" are both worth 21, and there aren't any more to add
+    fn top_words_takes_the_best_k() {
+        let dict = Dictionary::from_words(["cat", "tac", "at"], 3);
+        let b = board_with(&["cat"], vec![]);
+        assert_eq!(TopWords { k: 1 }.evaluate(&b, &dict), 21);
+        assert_eq!(TopWords { k: 2 }.evaluate(&b, &dict), 42);
+        assert_eq!(TopWords { k: 5 }.evaluate(&b, &dict), 42);
+    }
+
+    #[test]
+    /// The `z` can't go anywhere and the `a` isn't worth enough, leaving `c` and `q`
+    fn letters_need_to_be_reachable() {
+        let dict = Dictionary::from_words(["cat"], 3);
+        let b = board_with(&["z.  ", "..qc", "...a"], vec![]);
+        assert_eq!(
+            HighValueLetters { min_value: 4 }.evaluate(&b, &dict),
+            (12 + 4) * 3
+        );
+    }
+
+    #[test]
+    fn multipliers_count_their_tiles() {
+        let dict = Dictionary::from_words(["cat"], 3);
+        let b = board_with(&["cat"], vec![(12, 0), (12, 2)]);
+        assert_eq!(LiveMultipliers.evaluate(&b, &dict), (4 + 2) * 3);
+    }
+}
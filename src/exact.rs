@@ -0,0 +1,170 @@
+use crate::board::{Board, FoundWord};
+use crate::dictionary::Dictionary;
+use crate::game::{wants_memory_estimate, GameResult};
+use crate::scoring::Scoring;
+use crate::tile::Tile;
+use crate::Args;
+
+use deepsize::DeepSizeOf;
+use indicatif::HumanCount;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Finds the provably best way to finish a board by trying every single move (no -c cut) from
+/// every board, depth first.
+///
+/// The search deepens one move at a time, and anything we've explored all the way to the end of
+/// the game is remembered by board id. Boards only ever lose tiles, so the best way to finish a
+/// board doesn't depend on how we got there, and each deeper pass only has to redo the parts of
+/// the tree that previous passes couldn't see the end of.
+pub struct ExactSolver<'a> {
+    dict: &'a Dictionary,
+    /// For every board we've completely explored: the most points left in it, and the move to
+    /// make to get them (`None` if there's nothing left to play).
+    memo: HashMap<u64, (u32, Option<FoundWord>)>,
+    /// How many boards we've looked at
+    pub searched: usize,
+}
+
+impl<'a> ExactSolver<'a> {
+    pub fn new(dict: &'a Dictionary) -> Self {
+        ExactSolver {
+            dict,
+            memo: HashMap::new(),
+            searched: 0,
+        }
+    }
+
+    /// The best possible rest-of-game from `board`, as a list of moves
+    pub fn solve(&mut self, board: &Board) -> Vec<FoundWord> {
+        let mut depth = 1;
+        while !self.solve_to_depth(board, depth).1 {
+            depth += 1;
+        }
+        self.best_line(board)
+    }
+
+    /// Look at most `depth` moves ahead of `board`. Gives back the best score we found, and if we
+    /// got to see the end of every line (in which case the score is exact).
+    pub fn solve_to_depth(&mut self, board: &Board, depth: usize) -> (u32, bool) {
+        if let Some((best, _)) = self.memo.get(&board.id) {
+            return (*best, true);
+        }
+
+        self.searched += 1;
+        let words = board.find_words(self.dict, usize::MAX);
+        if words.is_empty() {
+            self.memo.insert(board.id, (0, None));
+            return (0, true);
+        }
+
+        if depth == 0 {
+            return (0, false);
+        }
+
+        let mut best = 0;
+        let mut best_move = None;
+        let mut complete = true;
+        for found_word in words {
+            let (rest, rest_complete) =
+                self.solve_to_depth(&board.evolve_via(found_word.clone()), depth - 1);
+            complete &= rest_complete;
+
+            let total = found_word.score + rest;
+            if best_move.is_none() || total > best {
+                best = total;
+                best_move = Some(found_word);
+            }
+        }
+
+        if complete {
+            self.memo.insert(board.id, (best, best_move));
+        }
+        (best, complete)
+    }
+
+    /// Follow the remembered best moves from `board` to the end of the game
+    fn best_line(&self, board: &Board) -> Vec<FoundWord> {
+        let mut line = vec![];
+        let mut board = board.clone();
+        while let Some((_, Some(found_word))) = self.memo.get(&board.id) {
+            line.push(found_word.clone());
+            board = board.evolve_via(found_word.clone());
+        }
+        line
+    }
+}
+
+/// Play the whole game with the `ExactSolver`. Only sensible for small boards!
+pub fn play_game(
+    args: &Args,
+    dict: &Dictionary,
+    board: [[Tile; Board::WIDTH]; Board::HEIGHT],
+    mult_locs: Vec<(usize, usize)>,
+) -> GameResult {
+    let game_start_time = Instant::now();
+    let starting_board = Board::new_from(board, mult_locs, args.min_word_length)
+        .with_scoring(Scoring::from_args(args));
+    let mut solver = ExactSolver::new(dict);
+
+    let mut depth = 1;
+    loop {
+        let (best, complete) = solver.solve_to_depth(&starting_board, depth);
+        if !args.quiet {
+            println!(
+                "Depth {: >2}: best so far {} ; {} boards searched",
+                depth,
+                HumanCount(best as u64),
+                HumanCount(solver.searched as u64)
+            );
+        }
+        if complete {
+            break;
+        }
+        depth += 1;
+    }
+
+    let line = solver.best_line(&starting_board);
+    GameResult {
+        score: line.iter().map(|w| w.score).sum(),
+        moves: line,
+        elapsed: game_start_time.elapsed(),
+        estimated_memory: wants_memory_estimate(args).then(|| solver.memo.deep_size_of()),
+    }
+}
+
+#[cfg(test)]
+mod exact_tests {
+    use super::*;
+
+    #[test]
+    /// Playing "cats" with the multiplier and a "cat" (in either order) is worth 85. Anything
+    /// that uses the multiplier on a "cat" loses it for "cats", which is only worth 74.
+    fn finds_the_best_line() {
+        let dict = Dictionary::from_words(["cat", "cats"], 3);
+        let mut tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+        for (col, c) in "cat".chars().enumerate() {
+            tiles[Board::HEIGHT - 2][col] = c;
+        }
+        for (col, c) in "cats".chars().enumerate() {
+            tiles[Board::HEIGHT - 1][col] = c;
+        }
+        let start = Board::new_from(tiles, vec![(Board::HEIGHT - 1, 1)], 3);
+
+        let mut solver = ExactSolver::new(&dict);
+        let line = solver.solve(&start);
+        assert_eq!(line.len(), 2);
+
+        let end = line
+            .into_iter()
+            .fold(start, |board, found_word| board.evolve_via(found_word));
+        assert_eq!(end.get_score(), 85);
+    }
+
+    #[test]
+    fn nothing_to_play() {
+        let dict = Dictionary::from_words(["cat"], 3);
+        let start = Board::new_from([[Board::EMPTY; Board::WIDTH]; Board::HEIGHT], vec![], 3);
+        assert!(ExactSolver::new(&dict).solve(&start).is_empty());
+    }
+}
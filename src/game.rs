@@ -1,12 +1,22 @@
-use crate::board::{Board, FoundWord};
+use crate::board::{Board, FoundWord, FoundWords, WordSearch};
+use crate::branching::ChildLimits;
 use crate::dictionary::Dictionary;
+use crate::exact::{self, ExactSolver};
+use crate::mcts;
+use crate::scoring::Scoring;
+use crate::sweep;
+use crate::tile::Tile;
 use crate::Args;
 
+use clap::ValueEnum;
 use deepsize::DeepSizeOf;
-use indicatif::{HumanBytes, HumanCount, HumanDuration, ProgressBar, ProgressStyle};
+use indicatif::{HumanBytes, HumanCount, ProgressBar, ProgressStyle};
 use rayon::prelude::*;
+use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
-use std::time::Instant;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 #[cfg(target_os = "windows")]
 use mimalloc::MiMalloc;
@@ -15,17 +25,106 @@ use mimalloc::MiMalloc;
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
+/// Counters we keep track of while playing a game
+#[derive(Default)]
+struct Stats {
+    /// How many boards had to move to a new id, since a different board already had theirs. A
+    /// board we've already moved that turns up again doesn't count a second time.
+    id_collisions: AtomicUsize,
+    /// Boards we didn't bother with since they couldn't beat the best finished game
+    pruned: AtomicUsize,
+    /// Boards whose moves (or that themselves) got dropped to stay under our size limits
+    cut_short: AtomicUsize,
+    /// Boards small enough that we handed them to the `ExactSolver`
+    solved_exactly: AtomicUsize,
+    /// Paths to a word that did exactly the same thing as another path to it
+    merged_paths: AtomicUsize,
+    /// Moves --skip-commuted left for a sibling to play
+    commuted: AtomicUsize,
+    /// Pruned boards that a better route turned up for, so they got another look
+    requeued: AtomicUsize,
+}
+
+impl Stats {
+    /// Whether every move from every board that could beat the best finished game got played.
+    /// Anything cut short by -c, -g or --max-generations could have been the best one, and so
+    /// could a move --skip-commuted left out. Merged paths lose nothing, they all do the same.
+    fn is_exhaustive(&self) -> bool {
+        self.cut_short.load(Ordering::Relaxed) == 0 && self.commuted.load(Ordering::Relaxed) == 0
+    }
+}
+
+/// A running total of how much `all_boards` takes up, going by `DeepSizeOf`. Walking every board
+/// each generation to find out gets slow as the search grows, so only the boards a generation
+/// touched get measured again.
+#[derive(Default)]
+struct MemoryEstimate {
+    /// How big every board was the last time it was measured
+    sizes: HashMap<u64, usize>,
+    total: usize,
+    /// The biggest `total` got
+    peak: usize,
+}
+
+impl MemoryEstimate {
+    /// Measure every board in `ids` again
+    fn update(&mut self, all_boards: &HashMap<u64, Board>, ids: impl IntoIterator<Item = u64>) {
+        const ENTRY: usize = std::mem::size_of::<(u64, Board)>();
+        for id in ids {
+            let size = all_boards[&id].deep_size_of() - std::mem::size_of::<Board>() + ENTRY;
+            let was = self.sizes.insert(id, size).unwrap_or(0);
+            self.total = self.total - was + size;
+        }
+        // Room the hash table has kept for boards it hasn't got yet counts too
+        let spare = (all_boards.capacity() - all_boards.len()) * ENTRY;
+        self.peak = std::cmp::max(self.peak, self.total + spare);
+    }
+}
+
+/// What came of searching a board
+enum Searched {
+    /// Every move we could make from here (up to -c of them), less any --skip-commuted left out
+    Words(FoundWords),
+    /// The best possible way to finish the game from here
+    Solved(Vec<FoundWord>),
+}
+
+/// How a game went
+pub struct GameResult {
+    pub score: u32,
+    /// The moves to make, in order, to get `score`
+    pub moves: Vec<FoundWord>,
+    pub elapsed: Duration,
+    /// Roughly how many bytes the search's boards took up at their biggest, going by
+    /// `DeepSizeOf`. It's only the boards we keep, checked once a generation, so it misses
+    /// whatever was only around for part of a generation (like the boards a batch evolves before
+    /// they're recorded, or the word lists being searched). Working it out costs time, so it's only
+    /// there when something will look at it (see `wants_memory_estimate()`).
+    pub estimated_memory: Option<usize>,
+}
+
+/// Whether the estimated memory use is wanted: a sweep reports it, and so does --memory-debug
+pub fn wants_memory_estimate(args: &Args) -> bool {
+    args.memory_debug || sweep::wanted(args)
+}
+
 pub fn play_game(
     args: &Args,
-    board: [[char; Board::WIDTH]; Board::HEIGHT],
+    dict: &Dictionary,
+    board: [[Tile; Board::WIDTH]; Board::HEIGHT],
     mult_locs: Vec<(usize, usize)>,
-    game_start_time: Instant,
-) {
+) -> GameResult {
+    let game_start_time = Instant::now();
     let mut all_boards = HashMap::new();
     let mut terminal_boards = HashSet::new();
     let mut to_process = Vec::new();
+    let stats = Stats::default();
+    // Every board --prune has dropped, and its score at the time. `improve_route()` can still
+    // raise that score, and then it might be worth a look after all.
+    let mut pruned: HashMap<u64, u32> = HashMap::new();
 
-    let starting_board = Board::new_from(board, mult_locs, args.min_word_length);
+    let starting_board = Board::new_from(board, mult_locs, args.min_word_length)
+        .with_scoring(Scoring::from_args(args));
 
     to_process.push(starting_board.id);
     all_boards.insert(starting_board.id, starting_board);
@@ -36,8 +135,15 @@ pub fn play_game(
     .unwrap()
     .progress_chars("-> ");
 
-    let dict = Dictionary::new(&args);
+    let evaluator = args.evaluator.build(args.evaluator_top_k);
+    let child_limits = ChildLimits::from_args(args);
+    // One solver per worker thread, kept for the whole game so whatever it's worked out carries
+    // over: sibling boards (and their descendants) share most of their endings
+    let exact_solvers = (0..rayon::current_num_threads())
+        .map(|_| Mutex::new(ExactSolver::new(dict)))
+        .collect::<Vec<Mutex<ExactSolver>>>();
     let mut generation = 1_u32;
+    let mut memory_estimate = wants_memory_estimate(args).then(MemoryEstimate::default);
     while !to_process.is_empty() {
         let to_process_len = to_process.len() as u64;
         let bar: ProgressBar;
@@ -62,10 +168,11 @@ pub fn play_game(
         }
 
         // Search the boards in this generation, provided they're not somehow dupes
+        let generation_limit = child_limits.for_generation(generation, to_process.len());
         let newly_searched = to_process
             .par_iter()
             .map(|board_id| {
-                let b = all_boards.get(&board_id).unwrap();
+                let b = all_boards.get(board_id).unwrap();
 
                 if b.searched() {
                     bar.inc(1);
@@ -77,12 +184,37 @@ pub fn play_game(
                     return None;
                 }
 
-                let words = b.find_words(&dict, args.max_children);
+                // Few enough tiles left that we can just work out the best possible finish
+                if b.usable_tiles < args.exact_below {
+                    let thread = rayon::current_thread_index().unwrap_or(0);
+                    let line = exact_solvers[thread].lock().unwrap().solve(b);
+                    stats.solved_exactly.fetch_add(1, Ordering::Relaxed);
+                    bar.inc(1);
+                    return Some((*board_id, Searched::Solved(line)));
+                }
+
+                let max_children = child_limits.for_board(generation_limit, b);
+                let found = b.find_words_with(
+                    dict,
+                    WordSearch {
+                        top_n: max_children,
+                        skip_commuted: args.skip_commuted,
+                        distinct_effects: args.distinct_paths,
+                    },
+                );
+                if found.words.len() >= max_children {
+                    stats.cut_short.fetch_add(1, Ordering::Relaxed);
+                }
+                stats.merged_paths.fetch_add(
+                    found.words.iter().map(|w| w.paths as usize - 1).sum(),
+                    Ordering::Relaxed,
+                );
+                stats.commuted.fetch_add(found.commuted, Ordering::Relaxed);
                 bar.inc(1);
-                Some((board_id.clone(), words))
+                Some((*board_id, Searched::Words(found)))
             })
             .flatten()
-            .collect::<Vec<(u64, Vec<FoundWord>)>>();
+            .collect::<Vec<(u64, Searched)>>();
         bar.finish();
 
         /*
@@ -90,23 +222,38 @@ pub fn play_game(
         1. Update the Board (living in all_boards) with its word list
         2. If the board is terminal, update terminal_boards with its id
         3. Otherwise, emit the ID as a board to be used next to make the next generation
+        Boards that were solved exactly get their whole line played out here instead.
          */
-        let boards_to_work = newly_searched
+        let mut searched_ids = newly_searched
             .iter()
-            .map(|(board_id, new_words)| {
-                all_boards
-                    .entry(*board_id)
-                    .and_modify(|b| b.set_words(new_words.clone()));
+            .map(|(board_id, _)| *board_id)
+            .collect::<Vec<u64>>();
+        let boards_to_work = newly_searched
+            .into_iter()
+            .filter_map(|(board_id, searched)| match searched {
+                Searched::Words(found) => {
+                    let b = all_boards.get_mut(&board_id).unwrap();
+                    b.set_found(found);
 
-                if new_words.len() == 0 {
-                    terminal_boards.insert(*board_id);
+                    if b.is_terminal() {
+                        terminal_boards.insert(board_id);
+                        None
+                    } else if b.words().is_empty() {
+                        // Every move was left to a sibling, nothing to do here
+                        None
+                    } else {
+                        Some(board_id)
+                    }
+                }
+                Searched::Solved(line) => {
+                    let line_ids =
+                        record_line(&mut all_boards, board_id, line, args.verify_ids, &stats);
+                    terminal_boards.insert(*line_ids.last().unwrap());
+                    searched_ids.extend(line_ids);
                     None
-                } else {
-                    Some(*board_id)
                 }
             })
-            .flatten()
-            .collect::<HashSet<u64>>();
+            .collect::<Vec<u64>>();
 
         let bar: ProgressBar;
         if args.quiet {
@@ -117,76 +264,90 @@ pub fn play_game(
             bar.set_message("📈");
         }
 
-        let boards_to_iter = Vec::from_iter(boards_to_work.iter());
-        let new_to_process = boards_to_iter
+        let mut new_to_process = boards_to_work
             .chunks(args.evolution_batch_size)
-            .map(|boards| {
-                let boards_to_add = boards
+            .flat_map(|boards| {
+                let evolved = boards
                     .par_iter()
-                    .map(|b_id| {
+                    .flat_map(|b_id| {
                         let b = all_boards.get(b_id).unwrap();
 
+                        // An earlier batch found another way here, and it's been sent back to
+                        // be searched again (see `record_board()`)
+                        if !b.searched() {
+                            return vec![];
+                        }
+
                         // To keep all_boards references immutable, let's keep a separate list of all the
-                        // Boards we're going to add to all_boards.
-                        let mut new_boards: HashMap<u64, Board> = HashMap::new();
+                        // Boards we're going to add to all_boards. This is a Vec (rather than keyed by
+                        // id) so they come out in the same order every time.
+                        let mut new_boards: Vec<Board> = vec![];
+                        let mut new_board_idx: HashMap<u64, usize> = HashMap::new();
                         for found_word in b.words().clone() {
                             let new_board = b.evolve_via(found_word);
 
-                            // Now let's check if this new board is *actually* new
-                            if new_boards.contains_key(&new_board.id) {
-                                // TODO: Figure out if we want to replace all_boards[new_board.id] with this one
-                                // (e.g. for higher score) and what would need to happen if we did. Since this board state
-                                // hasn't been searched yet, maybe a simple swap is OK.
-
-                                // One of our siblings (with the same/higher score) has the same net-effect, skip this one
-                                continue;
-                            } else if boards_to_work.contains(&new_board.id) {
-                                // TODO: Figure out if we want to replace all_boards[new_board.id] with this one
-                                // (e.g. for higher score) and what would need to happen if we did. Since this board state
-                                // hasn't been searched yet, maybe a simple swap is OK.
-
-                                // b managed to evolve one of it siblings, skip it
-                                continue;
-                            } else if all_boards.contains_key(&new_board.id) {
-                                // TODO: Figure out if we want to replace all_boards[new_board.id] with this one
-                                // (e.g. for higher score) and what would need to happen if we did. Since this board state
-                                // **HAS** been searched, we'd need to update any descendants scores with the delta
-
-                                // This board was born in a previous generation
+                            // One of our siblings may have the same net-effect, only keep the best of them
+                            if let Some(&idx) = new_board_idx.get(&new_board.id) {
+                                let sibling = &new_boards[idx];
+                                if args.verify_ids && !sibling.same_state(&new_board) {
+                                    // Not actually the same board, record_board() will find it a new id
+                                    new_boards.push(new_board);
+                                    continue;
+                                }
+                                if sibling.get_score() < new_board.get_score() {
+                                    new_boards[idx] = new_board;
+                                }
                                 continue;
                             }
-                            new_boards.insert(new_board.id, new_board);
+                            new_board_idx.insert(new_board.id, new_boards.len());
+                            new_boards.push(new_board);
                         }
                         new_boards
                     })
-                    .flatten()
-                    .collect::<HashMap<u64, Board>>();
+                    .collect::<Vec<Board>>();
 
-                // Update to_process with all the new boards we found
-                let batch_new_to_process = boards_to_add
-                    .par_iter()
-                    .map(|(b_id, _)| b_id.clone())
+                // Whether these are actually new needs a look at (and maybe changes to) all_boards, so
+                // this part has to happen one board at a time.
+                let batch_new_to_process = evolved
+                    .into_iter()
+                    .filter_map(|new_board| {
+                        match record_board(&mut all_boards, new_board, args.verify_ids, &stats) {
+                            (id, true) => Some(id),
+                            (_, false) => None,
+                        }
+                    })
                     .collect::<Vec<u64>>();
 
-                // And update all_boards with all the new boards we found
-                all_boards.extend(boards_to_add);
                 bar.inc(boards.len() as u64);
                 batch_new_to_process
             })
-            .flatten()
-            .collect::<HashSet<u64>>();
+            .collect::<Vec<u64>>();
         bar.finish();
 
+        // Hash order isn't something we want leaking into results, so from here on out boards are
+        // always dealt with in id order.
+        new_to_process.sort_unstable();
+
+        // Boards never leave all_boards, so this (just before cleaning) is as big as it gets. The
+        // boards we searched got words and children, and the new ones are new. Boards that only
+        // got a better route can lose their move, which we don't notice, so that errs high.
+        if let Some(estimate) = &mut memory_estimate {
+            searched_ids.sort_unstable();
+            searched_ids.dedup();
+            estimate.update(&all_boards, searched_ids);
+            estimate.update(&all_boards, new_to_process.iter().copied());
+        }
+
         // Now clean up everything that's "dirty"
         let boards_to_clean = all_boards
             .keys()
             .collect::<Vec<&u64>>()
             .into_par_iter()
             .filter_map(|k| {
-                if new_to_process.contains(k) || !all_boards.get(k).unwrap().dirty() {
+                if new_to_process.binary_search(k).is_ok() || !all_boards.get(k).unwrap().dirty() {
                     None
                 } else {
-                    Some(k.clone())
+                    Some(*k)
                 }
             })
             .collect::<Vec<u64>>();
@@ -200,51 +361,109 @@ pub fn play_game(
             bar.set_message("🧹");
         }
 
-        for board_id in boards_to_clean {
-            all_boards.entry(board_id).and_modify(|b| b.clean());
+        for board_id in &boards_to_clean {
+            all_boards.entry(*board_id).and_modify(|b| b.clean());
             bar.inc(1);
         }
         bar.finish();
+        if let Some(estimate) = &mut memory_estimate {
+            estimate.update(&all_boards, boards_to_clean);
+        }
 
         if !args.quiet {
             println!();
         }
         generation += 1;
         if generation > args.max_generations {
+            stats
+                .cut_short
+                .fetch_add(new_to_process.len(), Ordering::Relaxed);
             break;
         }
 
-        to_process = Vec::from_iter(new_to_process.into_iter());
+        to_process = new_to_process;
+
+        if args.prune {
+            // A better route to a board we pruned means a better score, so it goes back in the
+            // running
+            let raised = pruned
+                .iter()
+                .filter(|(b_id, score)| all_boards.get(b_id).unwrap().get_score() > **score)
+                .map(|(b_id, _)| *b_id)
+                .collect::<Vec<u64>>();
+            if !raised.is_empty() {
+                stats.requeued.fetch_add(raised.len(), Ordering::Relaxed);
+                for b_id in &raised {
+                    pruned.remove(b_id);
+                }
+                to_process.extend(raised);
+                to_process.sort_unstable();
+                to_process.dedup();
+            }
+
+            // Anything that can't do better than a game we've already finished isn't worth the time
+            if let Some(best_terminal) = terminal_boards
+                .iter()
+                .map(|b_id| all_boards.get(b_id).unwrap().get_score())
+                .max()
+            {
+                let before = to_process.len();
+                to_process.retain(|b_id| {
+                    let b = all_boards.get(b_id).unwrap();
+                    if b.get_score() + b.upper_bound(dict.longest_word()) > best_terminal {
+                        return true;
+                    }
+                    pruned.insert(*b_id, b.get_score());
+                    false
+                });
+                stats
+                    .pruned
+                    .fetch_add(before - to_process.len(), Ordering::Relaxed);
+            }
+        }
 
         if to_process.len() > args.max_gen_size {
             /*
             Since we have too many, we need to pick some. Do that by sorting and truncating.
             Sorting order is:
+              - cumulative board score + what our evaluator thinks is left ; highest wins
               - cumulative board score ; highest wins
               - usable tiles remaining ; highest wins
               - board id ; lowest wins
             */
-            to_process.sort_by(|a, b| {
-                let board_a = all_boards.get(a).unwrap();
-                let board_b = all_boards.get(b).unwrap();
-                board_a
-                    .get_score()
-                    .cmp(&board_b.get_score())
+            let mut ranked = to_process
+                .par_iter()
+                .map(|b_id| {
+                    let b = all_boards.get(b_id).unwrap();
+                    (b.get_score() + evaluator.evaluate(b, dict), b)
+                })
+                .collect::<Vec<(u32, &Board)>>();
+            ranked.par_sort_by(|(estimate_a, board_a), (estimate_b, board_b)| {
+                estimate_a
+                    .cmp(estimate_b)
                     .reverse()
-                    .then(
-                        board_a
-                            .usable_tiles
-                            .cmp(&board_b.usable_tiles)
-                            .reverse()
-                            .then(a.cmp(&b)),
-                    )
+                    .then(board_a.get_score().cmp(&board_b.get_score()).reverse())
+                    .then(board_a.usable_tiles.cmp(&board_b.usable_tiles).reverse())
+                    .then(board_a.id.cmp(&board_b.id))
             });
-            to_process.truncate(args.max_gen_size);
+            stats
+                .cut_short
+                .fetch_add(to_process.len() - args.max_gen_size, Ordering::Relaxed);
+            let ranked = ranked.into_iter().map(|(_, b)| b).collect::<Vec<&Board>>();
+            to_process = match args.max_per_root {
+                Some(cap) => pick_diverse(&ranked, &all_boards, cap, args.max_gen_size),
+                None => ranked
+                    .iter()
+                    .take(args.max_gen_size)
+                    .map(|b| b.id)
+                    .collect(),
+            };
         }
     }
 
     let term_count = terminal_boards.len();
 
+    // Highest score wins, lowest id breaks any ties
     let mut final_term_boards = terminal_boards.into_iter().collect::<Vec<u64>>();
     final_term_boards.par_sort_by(|a, b| {
         all_boards
@@ -252,9 +471,10 @@ pub fn play_game(
             .unwrap()
             .get_score()
             .cmp(&all_boards.get(a).unwrap().get_score())
+            .then(a.cmp(b))
     });
 
-    let winner = all_boards.get(final_term_boards.get(0).unwrap()).unwrap();
+    let winner = all_boards.get(final_term_boards.first().unwrap()).unwrap();
 
     // From our winning terimal board, work backwards up to the starting board
     let mut winning_path = vec![];
@@ -264,10 +484,9 @@ pub fn play_game(
             break;
         }
 
-        winning_path.push(curr_board.evolved_via());
-        curr_board = all_boards
-            .get(&all_boards.get(&curr_board.id).unwrap().evolved_from())
-            .unwrap();
+        let parent = all_boards.get(&curr_board.evolved_from()).unwrap();
+        winning_path.push(curr_board.move_from(parent, dict));
+        curr_board = parent;
     }
     // Now reverse that so winning_path is a list of moves to make from the beginning
     winning_path.reverse();
@@ -277,11 +496,57 @@ pub fn play_game(
             "Found {} unique terminal boards",
             HumanCount(term_count as u64)
         );
+        if args.verify_ids {
+            println!(
+                "Detected {} board id collisions",
+                HumanCount(stats.id_collisions.load(Ordering::Relaxed) as u64)
+            );
+        }
+        if args.exact_below > 0 {
+            println!(
+                "Solved {} boards exactly",
+                HumanCount(stats.solved_exactly.load(Ordering::Relaxed) as u64)
+            );
+        }
+        if args.distinct_paths {
+            println!(
+                "Merged {} paths that had the same effect as another",
+                HumanCount(stats.merged_paths.load(Ordering::Relaxed) as u64)
+            );
+        }
+        if args.prune {
+            println!(
+                "Pruned {} boards that couldn't beat the best finished game",
+                HumanCount(stats.pruned.load(Ordering::Relaxed) as u64)
+            );
+            if stats.requeued.load(Ordering::Relaxed) > 0 {
+                println!(
+                    "Took another look at {} pruned boards that found a better route",
+                    HumanCount(stats.requeued.load(Ordering::Relaxed) as u64)
+                );
+            }
+            if stats.is_exhaustive() {
+                println!(
+                    "Nothing was cut short by -c, -g or --max-generations, or left out by \
+                     --skip-commuted: this is optimal"
+                );
+            }
+        }
     }
 
+    GameResult {
+        score: winner.get_score(),
+        moves: winning_path,
+        elapsed: game_start_time.elapsed(),
+        estimated_memory: memory_estimate.map(|estimate| estimate.peak),
+    }
+}
+
+/// Show the final score, and the moves it took to get there
+pub fn print_winning_line(score: u32, winning_path: &[FoundWord]) {
     println!(
         "{: >5} via {: >2} words",
-        HumanCount(winner.get_score() as u64),
+        HumanCount(score as u64),
         winning_path.len()
     );
 
@@ -295,10 +560,541 @@ pub fn play_game(
                 .collect::<Vec<String>>()
         )
     }
-    if !args.quiet {
-        println!(
-            "Finished playing in {}",
-            HumanDuration(game_start_time.elapsed())
+}
+
+/// Replay `winning_path` from the starting board, showing how each move scored
+pub fn print_explained_line(
+    args: &Args,
+    board: [[Tile; Board::WIDTH]; Board::HEIGHT],
+    mult_locs: Vec<(usize, usize)>,
+    winning_path: &[FoundWord],
+) {
+    let mut board = Board::new_from(board, mult_locs, args.min_word_length)
+        .with_scoring(Scoring::from_args(args));
+    for p in winning_path {
+        println!("  {}", board.explain_score(&p.word, &p.path));
+        board = board.evolve_via(p.clone());
+    }
+}
+
+/// How we go about searching for the best game
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Strategy {
+    /// Generation by generation, keeping the best -g boards each time
+    Beam,
+    /// Monte Carlo Tree Search: random playouts, steering towards the ones that went well
+    Mcts,
+    /// Try absolutely everything. Only sensible for small boards!
+    Exact,
+}
+
+impl Strategy {
+    pub fn play(
+        self,
+        args: &Args,
+        dict: &Dictionary,
+        board: [[Tile; Board::WIDTH]; Board::HEIGHT],
+        mult_locs: Vec<(usize, usize)>,
+    ) -> GameResult {
+        match self {
+            Strategy::Beam => play_game(args, dict, board, mult_locs),
+            Strategy::Mcts => mcts::play_game(args, dict, board, mult_locs),
+            Strategy::Exact => exact::play_game(args, dict, board, mult_locs),
+        }
+    }
+}
+
+/// The first move made on the way to `board`, as the id of the board that move made. The starting
+/// board is its own first move.
+fn first_move_of(all_boards: &HashMap<u64, Board>, board: &Board) -> u64 {
+    let mut curr = board;
+    while curr.evolved_from() != 0 {
+        let parent = all_boards.get(&curr.evolved_from()).unwrap();
+        if parent.evolved_from() == 0 {
+            break;
+        }
+        curr = parent;
+    }
+    curr.id
+}
+
+/// Take `size` boards from `ranked` (best first), but no more than `cap` that share a first move,
+/// so one good opening can't crowd out every other line. If that leaves room, the best of the
+/// boards we skipped over fill it.
+fn pick_diverse(
+    ranked: &[&Board],
+    all_boards: &HashMap<u64, Board>,
+    cap: usize,
+    size: usize,
+) -> Vec<u64> {
+    let mut per_root: HashMap<u64, usize> = HashMap::new();
+    let mut picked = Vec::with_capacity(size);
+    let mut skipped = vec![];
+    for b in ranked {
+        if picked.len() >= size {
+            break;
+        }
+        let count = per_root.entry(first_move_of(all_boards, b)).or_default();
+        if *count < cap {
+            *count += 1;
+            picked.push(b.id);
+        } else {
+            skipped.push(b.id);
+        }
+    }
+
+    let room = size - picked.len();
+    picked.extend(skipped.into_iter().take(room));
+    picked
+}
+
+/// Add `new_board` to `all_boards`, returning its id and whether it needs to be processed: it's
+/// never been seen before, or it has to be searched again now there's another way to it.
+///
+/// If we *have* seen it before (a transposition) we keep whichever route to it scores the most,
+/// and if that's the new one we push the improvement down to everything we've already evolved
+/// from it.
+///
+/// With `verify_ids` we don't trust that a matching id means a matching board; anything that
+/// collides with a different board gets moved along to its next id.
+fn record_board(
+    all_boards: &mut HashMap<u64, Board>,
+    mut new_board: Board,
+    verify_ids: bool,
+    stats: &Stats,
+) -> (u64, bool) {
+    if verify_ids {
+        let mut rekeyed = false;
+        while let Some(existing) = all_boards.get(&new_board.id) {
+            if existing.same_state(&new_board) {
+                break;
+            }
+            new_board.rekey();
+            rekeyed = true;
+        }
+        // Only a collision we haven't seen before ends up at an id nobody has yet
+        if rekeyed && !all_boards.contains_key(&new_board.id) {
+            stats.id_collisions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let id = new_board.id;
+    let from = new_board.evolved_from();
+    let via = new_board.evolved_via();
+    let score = new_board.get_score();
+
+    if let Some(parent) = all_boards.get_mut(&from) {
+        parent.add_child(id, via.score);
+    }
+
+    let existing = match all_boards.entry(id) {
+        Entry::Vacant(e) => {
+            e.insert(new_board);
+            return (id, true);
+        }
+        Entry::Occupied(e) => e.into_mut(),
+    };
+
+    // A second parent means the moves --skip-commuted left out might not get played from a
+    // sibling after all (that sibling only gets the old parent's score), so they need playing here
+    let search_again = existing.evolved_from() != from && existing.forget_footprint();
+
+    improve_route(all_boards, id, from, via, score);
+    (id, search_again)
+}
+
+/// Play `line` out from `board_id`, adding every board along the way to `all_boards` as though
+/// it had been searched and that was the only move it had. Gives back the id of every board on the
+/// line, from `board_id` to the one at the end.
+fn record_line(
+    all_boards: &mut HashMap<u64, Board>,
+    board_id: u64,
+    line: Vec<FoundWord>,
+    verify_ids: bool,
+    stats: &Stats,
+) -> Vec<u64> {
+    let mut board_id = board_id;
+    let mut line_ids = vec![board_id];
+    for found_word in line {
+        let board = all_boards.get_mut(&board_id).unwrap();
+        if !board.searched() {
+            board.set_words(vec![found_word.clone()]);
+        }
+        let next_board = board.evolve_via(found_word);
+        board_id = record_board(all_boards, next_board, verify_ids, stats).0;
+        line_ids.push(board_id);
+    }
+
+    let end = all_boards.get_mut(&board_id).unwrap();
+    if !end.searched() {
+        end.set_words(vec![]);
+    }
+    line_ids
+}
+
+/// We've found a route to `board_id` via `from` worth `score`. If that beats the route we already
+/// had, relink the board and give the same treatment to all of its children. Children only know
+/// what their move scored, so any that end up with a new parent forget how they got there (see
+/// `Board::move_from()`).
+fn improve_route(
+    all_boards: &mut HashMap<u64, Board>,
+    board_id: u64,
+    from: u64,
+    via: FoundWord,
+    score: u32,
+) {
+    let mut to_improve = vec![(board_id, from, Some(via), score)];
+
+    while let Some((board_id, from, via, score)) = to_improve.pop() {
+        let board = all_boards.get_mut(&board_id).unwrap();
+        if !board.is_better_route(from, score) {
+            continue;
+        }
+
+        board.relink(from, via, score);
+        to_improve.extend(
+            board
+                .children()
+                .iter()
+                .map(|&(child_id, move_score)| (child_id, board_id, None, score + move_score)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod game_tests {
+    use super::*;
+    use crate::position::Position;
+    use clap::Parser;
+
+    fn bottom_row_board(letters: &str) -> Board {
+        let mut tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+        for (col, c) in letters.chars().enumerate() {
+            tiles[Board::HEIGHT - 1][col] = c;
+        }
+        Board::new_from(tiles, vec![], 3)
+    }
+
+    fn word_at(word: &str, start_col: usize, score: u32) -> FoundWord {
+        FoundWord {
+            path: (0..word.len())
+                .map(|i| Position::new(Board::HEIGHT - 1, start_col + i))
+                .collect(),
+            word: word.to_string(),
+            score,
+            paths: 1,
+        }
+    }
+
+    fn record(all_boards: &mut HashMap<u64, Board>, new_board: Board) -> Option<u64> {
+        match record_board(all_boards, new_board, true, &Stats::default()) {
+            (id, true) => Some(id),
+            (_, false) => None,
+        }
+    }
+
+    #[test]
+    /// Arriving at an already searched board via a better route should relink it and bump the
+    /// scores of everything we've already evolved from it.
+    fn better_route_propagates() {
+        let mut all_boards = HashMap::new();
+        let start = bottom_row_board("catdog");
+        let start_id = start.id;
+        all_boards.insert(start_id, start.clone());
+
+        let cheap = start.evolve_via(word_at("cat", 0, 10));
+        let middle_id = record(&mut all_boards, cheap.clone()).unwrap();
+
+        let grandchild = cheap.evolve_via(word_at("dog", 3, 5));
+        let end_id = record(&mut all_boards, grandchild).unwrap();
+        assert_eq!(all_boards[&end_id].get_score(), 15);
+
+        // Same tiles as `cheap`, just worth more
+        let pricey = start.evolve_via(word_at("cat", 0, 30));
+        assert_eq!(record(&mut all_boards, pricey), None);
+
+        assert_eq!(all_boards[&middle_id].get_score(), 30);
+        assert_eq!(all_boards[&end_id].get_score(), 35);
+        assert_eq!(all_boards[&end_id].evolved_from(), middle_id);
+    }
+
+    #[test]
+    /// A board that finds a better parent only knows what the move from it scored, so the move
+    /// itself gets worked out again from the parent
+    fn relinked_board_finds_its_move_again() {
+        let dict = Dictionary::from_words(["cat", "dog"], 3);
+        let mut all_boards = HashMap::new();
+        let start = bottom_row_board("cat  dog");
+        all_boards.insert(start.id, start.clone());
+        let real = |board: &Board, word: &str| {
+            board
+                .find_words(&dict, usize::MAX)
+                .into_iter()
+                .find(|w| w.word == word)
+                .unwrap()
+        };
+
+        // The end board, cheapest by way of "cat" first, so "dog" first is where it's linked
+        let cheap_cat = start.evolve_via(word_at("cat", 0, 1));
+        let dog = start.evolve_via(real(&start, "dog"));
+        record(&mut all_boards, cheap_cat.clone()).unwrap();
+        record(&mut all_boards, dog.clone()).unwrap();
+        let end_id = record(
+            &mut all_boards,
+            cheap_cat.evolve_via(real(&cheap_cat, "dog")),
+        );
+        let end_id = end_id.unwrap();
+        assert_eq!(
+            record(&mut all_boards, dog.evolve_via(real(&dog, "cat"))),
+            None
         );
+        assert_eq!(all_boards[&end_id].evolved_from(), dog.id);
+
+        // Now "cat" first is worth far more, and the end board moves over to it
+        let pricey_cat = start.evolve_via(word_at("cat", 0, 100));
+        assert_eq!(record(&mut all_boards, pricey_cat), None);
+        let end = &all_boards[&end_id];
+        assert_eq!(end.evolved_from(), cheap_cat.id);
+        let dog_after_cat = real(&cheap_cat, "dog");
+        assert_eq!(end.get_score(), 100 + dog_after_cat.score);
+        assert_eq!(
+            end.move_from(&all_boards[&cheap_cat.id], &dict),
+            dog_after_cat
+        );
+    }
+
+    #[test]
+    /// "emu" then "dog" leaves "cat" for a sibling, since "emu" then "cat" then "dog" gets there
+    /// too. "dog" then "emu" is another way to the same board, and from there "cat" touches the
+    /// last move, so the board can't leave it out any more and needs searching again.
+    fn second_parent_stops_skipping_commuted_moves() {
+        let dict = Dictionary::from_words(["cat", "dog", "emu"], 3);
+        let mut tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+        for (col, c) in "emu".chars().enumerate() {
+            tiles[Board::HEIGHT - 2][col] = c;
+        }
+        for (col, c) in "cat  dog".chars().enumerate() {
+            tiles[Board::HEIGHT - 1][col] = c;
+        }
+        let start = Board::new_from(tiles, vec![], 3);
+        let play = |board: &Board, word: &str| {
+            let found_word = board
+                .find_words(&dict, usize::MAX)
+                .into_iter()
+                .find(|w| w.word == word)
+                .unwrap();
+            board.evolve_via(found_word)
+        };
+        let search = |board: &Board| {
+            board.find_words_with(
+                &dict,
+                WordSearch {
+                    skip_commuted: true,
+                    ..WordSearch::top(usize::MAX)
+                },
+            )
+        };
+
+        let mut all_boards = HashMap::new();
+        all_boards.insert(start.id, start.clone());
+        let emu = play(&start, "emu");
+        let dog = play(&start, "dog");
+        record(&mut all_boards, emu.clone()).unwrap();
+        record(&mut all_boards, dog.clone()).unwrap();
+
+        let only_cat = record(&mut all_boards, play(&emu, "dog")).unwrap();
+        let found = search(&all_boards[&only_cat]);
+        assert!(found.words.is_empty());
+        assert_eq!(found.commuted, 1);
+        all_boards.get_mut(&only_cat).unwrap().set_found(found);
+        assert!(!all_boards[&only_cat].is_terminal());
+
+        assert_eq!(record(&mut all_boards, play(&dog, "emu")), Some(only_cat));
+        assert!(!all_boards[&only_cat].searched());
+        let found = search(&all_boards[&only_cat]);
+        assert_eq!(found.commuted, 0);
+        assert_eq!(found.words.len(), 1);
+        assert_eq!(found.words[0].word, "cat");
+
+        // The same parent again changes nothing
+        all_boards.get_mut(&only_cat).unwrap().set_found(found);
+        assert_eq!(record(&mut all_boards, play(&dog, "emu")), None);
+        assert!(all_boards[&only_cat].searched());
+    }
+
+    #[test]
+    /// With a cap of one per first move, the runner up from a different opening beats the second
+    /// best line from the best one; left over room still goes to the best of the rest.
+    fn diverse_picks_spread_across_first_moves() {
+        let mut all_boards = HashMap::new();
+        let start = bottom_row_board("catdogpig");
+        all_boards.insert(start.id, start.clone());
+
+        let cat = start.evolve_via(word_at("cat", 0, 10));
+        let dog = start.evolve_via(word_at("dog", 3, 5));
+        record(&mut all_boards, cat.clone()).unwrap();
+        record(&mut all_boards, dog.clone()).unwrap();
+
+        let cat_dog = record(&mut all_boards, cat.evolve_via(word_at("dog", 3, 30))).unwrap();
+        let cat_pig = record(&mut all_boards, cat.evolve_via(word_at("pig", 6, 20))).unwrap();
+        let dog_pig = record(&mut all_boards, dog.evolve_via(word_at("pig", 6, 1))).unwrap();
+        assert_eq!(first_move_of(&all_boards, &all_boards[&cat_pig]), cat.id);
+
+        let ranked = [cat_dog, cat_pig, dog_pig]
+            .iter()
+            .map(|id| &all_boards[id])
+            .collect::<Vec<&Board>>();
+        assert_eq!(
+            pick_diverse(&ranked, &all_boards, 1, 2),
+            vec![cat_dog, dog_pig]
+        );
+        assert_eq!(
+            pick_diverse(&ranked, &all_boards, 1, 3),
+            vec![cat_dog, dog_pig, cat_pig]
+        );
+    }
+
+    #[test]
+    /// A worse route to a board we already know about changes nothing
+    fn worse_route_ignored() {
+        let mut all_boards = HashMap::new();
+        let start = bottom_row_board("catdog");
+        all_boards.insert(start.id, start.clone());
+
+        let child_id = record(&mut all_boards, start.evolve_via(word_at("cat", 0, 30))).unwrap();
+        assert_eq!(
+            record(&mut all_boards, start.evolve_via(word_at("cat", 0, 10))),
+            None
+        );
+        assert_eq!(all_boards[&child_id].get_score(), 30);
+    }
+
+    #[test]
+    /// Two different boards that happen to share an id should both be kept when we're verifying
+    /// ids, and the collision should be counted.
+    fn colliding_ids_kept_apart() {
+        let mut all_boards = HashMap::new();
+        let stats = Stats::default();
+        let start = bottom_row_board("catdog");
+        all_boards.insert(start.id, start.clone());
+
+        let cat_gone = start.evolve_via(word_at("cat", 0, 10));
+        let mut dog_gone = start.evolve_via(word_at("dog", 3, 10));
+        dog_gone.id = cat_gone.id;
+
+        let (cat_id, _) = record_board(&mut all_boards, cat_gone.clone(), true, &stats);
+        let (dog_id, _) = record_board(&mut all_boards, dog_gone.clone(), true, &stats);
+        assert_ne!(cat_id, dog_id);
+        assert!(all_boards[&dog_id].same_state(&dog_gone));
+        assert_eq!(stats.id_collisions.load(Ordering::Relaxed), 1);
+
+        // Seeing the same board again should walk to the same id rather than a third one
+        dog_gone.id = cat_gone.id;
+        assert_eq!(
+            record_board(&mut all_boards, dog_gone, true, &stats),
+            (dog_id, false)
+        );
+        assert_eq!(all_boards.len(), 3);
+        assert_eq!(stats.id_collisions.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    /// Playing "cat" off the top or the bottom row leaves the same letters behind, but only
+    /// playing it off the top keeps the multiplier around for "cats". When only the tiles went into
+    /// the id these were the same board and the (better scoring) bottom play won, losing the best
+    /// line: 21 + 64 beats 42 + 32.
+    fn multipliers_are_part_of_identity() {
+        let dict = Dictionary::from_words(["cat", "cats"], 3);
+        let mut tiles = [[Board::EMPTY; Board::WIDTH]; Board::HEIGHT];
+        for (col, c) in "cat".chars().enumerate() {
+            tiles[Board::HEIGHT - 2][col] = c;
+        }
+        for (col, c) in "cats".chars().enumerate() {
+            tiles[Board::HEIGHT - 1][col] = c;
+        }
+        let start = Board::new_from(tiles, vec![(Board::HEIGHT - 1, 1)], 3);
+
+        let words = start.find_words(&dict, usize::MAX);
+        let cat_on_row = |row: usize| {
+            words
+                .iter()
+                .find(|w| w.word == "cat" && w.path.iter().all(|p| p.row as usize == row))
+                .unwrap()
+                .clone()
+        };
+        let keeps_mult = start.evolve_via(cat_on_row(Board::HEIGHT - 2));
+        let loses_mult = start.evolve_via(cat_on_row(Board::HEIGHT - 1));
+
+        let mut all_boards = HashMap::new();
+        all_boards.insert(start.id, start.clone());
+        assert!(record(&mut all_boards, keeps_mult.clone()).is_some());
+        assert!(record(&mut all_boards, loses_mult.clone()).is_some());
+
+        let best_finish = |b: &Board| {
+            b.find_words(&dict, usize::MAX)
+                .into_iter()
+                .map(|w| b.evolve_via(w).get_score())
+                .max()
+                .unwrap()
+        };
+        assert_eq!(best_finish(&keeps_mult), 85);
+        assert_eq!(best_finish(&loses_mult), 74);
+    }
+
+    #[test]
+    /// The upper bound has to be at least as good as the best line we can actually play, and
+    /// nothing once there's nothing left to play.
+    fn upper_bound_is_optimistic() {
+        let dict = Dictionary::from_words(["cat", "cats"], 3);
+        let start = bottom_row_board("cats");
+        assert!(start.upper_bound(dict.longest_word()) >= 32);
+
+        let words = start.find_words(&dict, usize::MAX);
+        let cats = words.iter().find(|w| w.word == "cats").unwrap();
+        assert_eq!(
+            start
+                .evolve_via(cats.clone())
+                .upper_bound(dict.longest_word()),
+            0
+        );
+    }
+
+    #[test]
+    /// With -c and -g too big to cut anything, pruning only drops boards that can't win, so the
+    /// beam search has to find the best game there is. On this board some of the boards that get
+    /// pruned turn up again later by a better route, and get another look.
+    fn pruned_search_is_optimal() {
+        let args =
+            Args::parse_from("spell-tower-solver -q --prune -c 1000 -g 1000000".split_whitespace());
+        let dict = Dictionary::from_words(
+            [
+                "cat", "cats", "act", "acts", "dog", "dogs", "god", "gods", "tao", "sag", "tag",
+                "tags", "cog", "cogs", "scat", "goad", "toad", "toads", "coat", "coats", "dots",
+                "dot", "sod", "ado", "ads", "stag", "goats",
+            ],
+            3,
+        );
+        let mut tiles = [[Tile::from(Board::EMPTY); Board::WIDTH]; Board::HEIGHT];
+        for (row, letters) in ["tocs", "asto", "dadg"].iter().enumerate() {
+            for (col, c) in letters.chars().enumerate() {
+                tiles[Board::HEIGHT - 3 + row][col] = c.into();
+            }
+        }
+        let mults = vec![(Board::HEIGHT - 3, 2)];
+
+        let result = play_game(&args, &dict, tiles, mults.clone());
+        let best = ExactSolver::new(&dict).solve(&Board::new_from(tiles, mults, 3));
+        assert_eq!(result.score, best.iter().map(|w| w.score).sum::<u32>());
+        assert_eq!(result.score, 181);
+    }
+
+    #[test]
+    /// Merging paths that do the same thing loses nothing, leaving moves out does
+    fn merged_paths_are_still_exhaustive() {
+        let stats = Stats::default();
+        stats.merged_paths.fetch_add(3, Ordering::Relaxed);
+        assert!(stats.is_exhaustive());
+        stats.commuted.fetch_add(1, Ordering::Relaxed);
+        assert!(!stats.is_exhaustive());
     }
 }
@@ -0,0 +1,214 @@
+pub mod bitboard;
+pub mod board;
+pub mod branching;
+pub mod calibrate;
+pub mod dictionary;
+pub mod evaluator;
+pub mod exact;
+pub mod game;
+pub mod mcts;
+pub mod position;
+pub mod scoring;
+pub mod sweep;
+pub mod tile;
+
+use clap::{Parser, Subcommand};
+use clio::*;
+use evaluator::Evaluator;
+use game::Strategy;
+use scoring::Scoring;
+use serde::{Deserialize, Serialize};
+use sweep::SweepFormat;
+use tile::Tile;
+
+/// Figure out the optimial set of moves in a game of SpellTower
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
+pub struct Args {
+    /// Path of the dictionary to use
+    #[arg(long, default_value = "nwl/nwl2023.txt")]
+    pub dict_path: String,
+
+    /// Starting max number of children each board can spawn: shorthand for --sweep-children from
+    /// here up to -c. Setting this forces quiet mode!
+    #[arg(short = 's', long)]
+    pub start_max_children: Option<usize>,
+
+    /// Maximum number of children each board can spawn.
+    #[arg(short = 'c', long, default_value_t = 5)]
+    pub max_children: usize,
+
+    /// Max children per generation, e.g. 10,8,5: generation 1 gets 10, generation 2 gets 8 and
+    /// every generation after that gets 5. Can't be used with -c, -s or --sweep-children.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        conflicts_with_all = ["max_children", "start_max_children", "sweep_children"]
+    )]
+    pub children_schedule: Vec<usize>,
+
+    /// Most new boards any one generation should spawn, shared out between the boards in it. Only
+    /// ever narrows -c / --children-schedule, and caps --multiplier-bonus too.
+    #[arg(long)]
+    pub gen_board_budget: Option<usize>,
+
+    /// Extra children a board gets for every multiplier still on it
+    #[arg(long, default_value_t = 0)]
+    pub multiplier_bonus: usize,
+
+    /// Scoring file with letter values and rules to use instead of the standard ones (see
+    /// sample-input/scoring.ron)
+    #[arg(long, global = true, value_parser = Scoring::load)]
+    pub scoring: Option<&'static Scoring>,
+
+    /// The board shows Qu rather than Q: every q on the board is one tile that spells qu
+    #[arg(long, default_value_t = false)]
+    pub qu: bool,
+
+    /// Minimum length of a word we'll consider valid
+    #[arg(short = 'w', long, default_value_t = 3)]
+    pub min_word_length: usize,
+
+    /// Show memory debugging info
+    #[arg(long, default_value_t = false)]
+    pub memory_debug: bool,
+
+    /// When evolving, how many boards to do in parallel
+    #[arg(long, default_value_t = 100)]
+    pub evolution_batch_size: usize,
+
+    /// Quiet - don't show any output: overrides --memory-debug
+    #[arg(short, long, default_value_t = false)]
+    pub quiet: bool,
+
+    /// After the winning line, show where the points for each move in it came from
+    #[arg(long, default_value_t = false)]
+    pub explain: bool,
+
+    /// Input board
+    #[clap(value_parser, default_value = "-")]
+    pub input_f: Input,
+
+    /// Max number of boards to process in any given generation
+    #[arg(short = 'g', long, default_value_t = 1_000_000)]
+    pub max_gen_size: usize,
+
+    /// Max number of generations. When we hit this generation we'll just stop.
+    #[arg(long, default_value_t = u32::MAX)]
+    pub max_generations: u32,
+
+    /// When a generation has more than -g boards, keep at most this many that share the same
+    /// first move (topping up with the best of the rest if there's room), so the search doesn't
+    /// all pile onto one line
+    #[arg(long)]
+    pub max_per_root: Option<usize>,
+
+    /// Of two moves that don't touch each other, only play them in one order (the other order ends
+    /// up on exactly the same board). Nothing is lost so long as neither -c nor -g is cutting
+    /// anything off: -c can drop a move from the parent, and -g can drop the one board that played
+    /// the moves in the order we kept.
+    #[arg(long, default_value_t = false)]
+    pub skip_commuted: bool,
+
+    /// Only keep one path for each word and set of tiles it destroys, so -c is spent on moves
+    /// that actually differ
+    #[arg(long, default_value_t = false)]
+    pub distinct_paths: bool,
+
+    /// Don't trust board ids alone: check the tiles match whenever two boards share an id, and
+    /// keep them apart if they don't
+    #[arg(long, default_value_t = false)]
+    pub verify_ids: bool,
+
+    /// Skip any board that can't possibly beat the best finished game we've found so far. With
+    /// big enough -c and -g this finds the optimal game.
+    #[arg(long, default_value_t = false)]
+    pub prune: bool,
+
+    /// How to rank boards when a generation has more than -g of them
+    #[arg(long, value_enum, default_value_t = Evaluator::None)]
+    pub evaluator: Evaluator,
+
+    /// How many words the top-words evaluator adds up
+    #[arg(long, default_value_t = 3)]
+    pub evaluator_top_k: usize,
+
+    /// How to search for the best game
+    #[arg(long, value_enum, default_value_t = Strategy::Beam)]
+    pub strategy: Strategy,
+
+    /// How many games to play out with --strategy mcts
+    #[arg(long, default_value_t = 10_000)]
+    pub mcts_iterations: usize,
+
+    /// How much --strategy mcts favors trying new moves over the ones that have gone well
+    #[arg(long, default_value_t = std::f64::consts::SQRT_2)]
+    pub mcts_exploration: f64,
+
+    /// Once a board has fewer than this many usable tiles, work out the best possible way to
+    /// finish it rather than searching it generation by generation. 0 (the default) turns this
+    /// off.
+    #[arg(long, default_value_t = 0)]
+    pub exact_below: usize,
+
+    /// Seed for anything random. Same seed, same game.
+    #[arg(long, default_value_t = 1)]
+    pub seed: u64,
+
+    /// Play a game for each of these -c values, and report them all in a table. Forces quiet mode!
+    #[arg(long, value_delimiter = ',')]
+    pub sweep_children: Vec<usize>,
+
+    /// Play a game for each of these -g values, and report them all in a table. Forces quiet mode!
+    #[arg(long, value_delimiter = ',')]
+    pub sweep_gen_sizes: Vec<usize>,
+
+    /// Play a game with each of these strategies, and report them all in a table. Forces quiet
+    /// mode!
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub sweep_strategies: Vec<Strategy>,
+
+    /// How to lay out the table of sweep results. Its est. memory column is how much the boards
+    /// a game kept took up at their biggest: anything that was only around for part of a
+    /// generation isn't in it, so the real peak is higher.
+    #[arg(long, value_enum, default_value_t = SweepFormat::Markdown)]
+    pub sweep_format: SweepFormat,
+
+    /// Don't actually run anything, just do a size test
+    #[arg(long, default_value_t = false)]
+    pub size_test: bool,
+
+    /// Something to do other than playing the input board
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Check our scoring (or --scoring) against a file of scores seen in real games, rather than
+    /// playing a game
+    Calibrate {
+        /// Observations to check, see sample-input/observations.ron
+        observations: String,
+
+        /// Also work out the letter values and rules that best fit the scores
+        #[arg(long, default_value_t = false)]
+        fit: bool,
+    },
+}
+
+/// A board as it comes in from a file: its tiles, and where the multipliers are. Tiles can be a
+/// char (`'a'`, `'ñ'`) or, for tiles with more than one letter on, a string (`"qu"`).
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InputBoard {
+    pub board: [[Tile; board::Board::WIDTH]; board::Board::HEIGHT],
+    pub mults: Vec<(usize, usize)>,
+}
+
+impl InputBoard {
+    /// For --qu, see `Tile::as_qu()`
+    pub fn with_qu_tiles(mut self) -> Self {
+        self.board = self.board.map(|row| row.map(Tile::as_qu));
+        self
+    }
+}
@@ -1,69 +1,17 @@
-pub mod board;
-pub mod dictionary;
-mod game;
-pub mod position;
-
 use std::io::Read;
 
-use board::Board;
 use clap::Parser;
-use clio::*;
 use deepsize::DeepSizeOf;
-use dictionary::Dictionary;
-use indicatif::HumanDuration;
-use serde::{Deserialize, Serialize};
-
-/// Figure out the optimial set of moves in a game of SpellTower
-#[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-pub struct Args {
-    /// Path of the dictionary to use
-    #[arg(long, default_value = "nwl/nwl2023.txt")]
-    dict_path: String,
-
-    /// Starting max number of children each board can spawn. Setting this forces quiet mode!
-    #[arg(short = 's', long)]
-    start_max_children: Option<usize>,
-
-    /// Maximum number of children each board can spawn.
-    #[arg(short = 'c', long, default_value_t = 5)]
-    max_children: usize,
-
-    /// Minimum length of a word we'll consider valid
-    #[arg(short = 'w', long, default_value_t = 3)]
-    min_word_length: usize,
-
-    /// Show memory debugging info
-    #[arg(long, default_value_t = false)]
-    memory_debug: bool,
-
-    /// When evolving, how many boards to do in parallel
-    #[arg(long, default_value_t = 100)]
-    evolution_batch_size: usize,
-
-    /// Quiet - don't show any output: overrides --memory-debug
-    #[arg(short, long, default_value_t = false)]
-    quiet: bool,
-
-    /// Input board
-    #[clap(value_parser, default_value = "-")]
-    input_f: Input,
-
-    /// Max number of boards to process in any given generation
-    #[arg(short = 'g', long, default_value_t = 1_000_000)]
-    max_gen_size: usize,
-
-    /// Max number of generations. When we hit this generation we'll just stop.
-    #[arg(long, default_value_t = u32::MAX)]
-    max_generations: u32,
-
-    /// Don't actually run anything, just do a size test
-    #[arg(long, default_value_t = false)]
-    size_test: bool,
-}
+use indicatif::{HumanBytes, HumanDuration};
+use spell_tower_solver::board::Board;
+use spell_tower_solver::dictionary::Dictionary;
+use spell_tower_solver::game::{print_explained_line, print_winning_line};
+use spell_tower_solver::scoring::Scoring;
+use spell_tower_solver::tile::Tile;
+use spell_tower_solver::{calibrate, sweep, Args, Command, InputBoard};
 
 fn size_test(args: Args) {
-    let boards = vec![
+    let boards: Vec<[[char; Board::WIDTH]; Board::HEIGHT]> = vec![
         [
             "i.ssbtpod".chars().collect::<Vec<char>>().try_into().unwrap(),
             "mcisneice".chars().collect::<Vec<char>>().try_into().unwrap(),
@@ -103,7 +51,14 @@ fn size_test(args: Args) {
     for board in boards {
         println!("\nBoard\n----------------------------------------");
         println!("    input board = {} bytes", &board.deep_size_of());
-        let mut b = Board::new_from(board, mult_locs.clone(), 3);
+        let mut input = InputBoard {
+            board: board.map(|row| row.map(Tile::from)),
+            mults: mult_locs.clone(),
+        };
+        if args.qu {
+            input = input.with_qu_tiles();
+        }
+        let mut b = Board::new_from(input.board, input.mults, 3);
         println!("             id = {}", b.id);
         println!("  board pre-pop = {} bytes", b.deep_size_of());
         let words = b.find_words(&dict, 100_000);
@@ -129,19 +84,17 @@ fn size_test(args: Args) {
         println!("        w1.word = {}", w1.word.deep_size_of());
         println!("       w1.score = {}", w1.score.deep_size_of());
 
-        b.set_words(words);
+        b.set_words(words.clone());
         println!("  board ful-pop = {} bytes", b.deep_size_of());
+        for found_word in words {
+            b.add_child(b.evolve_via(found_word.clone()).id, found_word.score);
+        }
+        println!("  board w/ kids = {} bytes", b.deep_size_of());
         b.clean();
         println!("  board cleaned = {} bytes", b.deep_size_of());
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct InputBoard {
-    board: [[char; Board::WIDTH]; Board::HEIGHT],
-    mults: Vec<(usize, usize)>,
-}
-
 fn main() {
     let mut args = Args::parse();
 
@@ -150,17 +103,25 @@ fn main() {
         return;
     }
 
+    if let Some(Command::Calibrate { observations, fit }) = &args.command {
+        calibrate::run(observations, Scoring::from_args(&args), *fit);
+        return;
+    }
+
     let mut input_str = String::new();
     args.input_f
         .read_to_string(&mut input_str)
         .unwrap_or_else(|e| panic!("Error reading {}: {}", args.input_f.path(), e));
-    let input_board: InputBoard = ron::from_str(&input_str).unwrap_or_else(|e| {
+    let mut input_board: InputBoard = ron::from_str(&input_str).unwrap_or_else(|e| {
         panic!(
             "{} doesn't look like the right kind of file: {}",
             args.input_f.path(),
             e
         )
     });
+    if args.qu {
+        input_board = input_board.with_qu_tiles();
+    }
 
     /*
     let tiles = input_board
@@ -171,21 +132,28 @@ fn main() {
     */
 
     let game_run_time = std::time::Instant::now();
-    if let Some(start) = args.start_max_children {
-        for child_count in start..=args.max_children {
-            args.max_children = child_count;
-            game::play_game(
-                &args,
-                input_board.board,
-                input_board.mults.clone(),
-                game_run_time,
-            )
-        }
-    } else {
-        game::play_game(&args, input_board.board, input_board.mults.clone(), game_run_time)
+
+    if sweep::wanted(&args) {
+        args.quiet = true;
+        let dict = Dictionary::new(&args);
+        sweep::run(&mut args, &dict, input_board.board, input_board.mults);
+        return;
+    }
+
+    let dict = Dictionary::new(&args);
+    let result = args
+        .strategy
+        .play(&args, &dict, input_board.board, input_board.mults.clone());
+    print_winning_line(result.score, &result.moves);
+    if args.explain {
+        print_explained_line(&args, input_board.board, input_board.mults, &result.moves);
     }
 
     if !args.quiet {
+        println!("Finished playing in {}", HumanDuration(result.elapsed));
+        if let Some(bytes) = result.estimated_memory {
+            println!("Estimated memory {}", HumanBytes(bytes as u64));
+        }
         println!("Finished in {}", HumanDuration(game_run_time.elapsed()));
     }
 }
@@ -0,0 +1,271 @@
+use crate::board::{Board, FoundWord};
+use crate::dictionary::Dictionary;
+use crate::game::{wants_memory_estimate, GameResult};
+use crate::scoring::Scoring;
+use crate::tile::Tile;
+use crate::Args;
+
+use deepsize::DeepSizeOf;
+use indicatif::{HumanCount, ProgressBar, ProgressStyle};
+use std::time::Instant;
+
+/// A small xorshift64* generator. Playouts need to be random, but the same seed should always
+/// play out the same way.
+pub struct Rng(u64);
+
+impl Rng {
+    /// Seeds 0 and 1 give the same numbers: xorshift gets stuck on 0 forever, so it's bumped up
+    pub fn new(seed: u64) -> Self {
+        Rng(std::cmp::max(seed, 1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    /// Something in 0..n
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+struct Node {
+    board: Board,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Every move from here (up to -c of them), kept for playouts to start from
+    moves: Vec<FoundWord>,
+    /// Indexes into `moves` of the ones that don't have a child node yet
+    untried: Vec<usize>,
+    visits: u32,
+    total_score: f64,
+}
+
+impl Node {
+    fn new(board: Board, parent: Option<usize>, dict: &Dictionary, max_children: usize) -> Self {
+        let moves = board.find_words(dict, max_children);
+        Node {
+            board,
+            parent,
+            children: vec![],
+            untried: (0..moves.len()).collect(),
+            moves,
+            visits: 0,
+            total_score: 0.0,
+        }
+    }
+}
+
+/// Play the game with Monte Carlo Tree Search. Each iteration walks down the tree picking
+/// children by UCT, adds one new child, plays randomly from there until there are no words left,
+/// and feeds the final score back up the tree.
+pub fn play_game(
+    args: &Args,
+    dict: &Dictionary,
+    board: [[Tile; Board::WIDTH]; Board::HEIGHT],
+    mult_locs: Vec<(usize, usize)>,
+) -> GameResult {
+    let game_start_time = Instant::now();
+    let mut rng = Rng::new(args.seed);
+
+    let starting_board = Board::new_from(board, mult_locs, args.min_word_length)
+        .with_scoring(Scoring::from_args(args));
+    let mut tree = vec![Node::new(starting_board, None, dict, args.max_children)];
+
+    let mut best_score = 0;
+    let mut best_line = vec![];
+
+    let bar = if args.quiet {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(args.mcts_iterations as u64)
+    };
+    bar.set_style(
+        ProgressStyle::with_template(
+            "{msg} {elapsed} {wide_bar:.blue} {human_pos:>}/{human_len} @ {per_sec}",
+        )
+        .unwrap()
+        .progress_chars("-> "),
+    );
+    bar.set_message("🎲");
+
+    for _ in 0..args.mcts_iterations {
+        // Selection: head down through fully expanded nodes
+        let mut node_idx = 0;
+        while tree[node_idx].untried.is_empty() && !tree[node_idx].children.is_empty() {
+            node_idx = select_child(&tree, node_idx, best_score, args.mcts_exploration);
+        }
+
+        // Expansion: try out one new move from here
+        if !tree[node_idx].untried.is_empty() {
+            let untried_idx = rng.below(tree[node_idx].untried.len());
+            let move_idx = tree[node_idx].untried.swap_remove(untried_idx);
+            let found_word = tree[node_idx].moves[move_idx].clone();
+            let child_board = tree[node_idx].board.evolve_via(found_word);
+
+            let child_idx = tree.len();
+            tree.push(Node::new(
+                child_board,
+                Some(node_idx),
+                dict,
+                args.max_children,
+            ));
+            tree[node_idx].children.push(child_idx);
+            node_idx = child_idx;
+        }
+
+        // Playout: random moves until there's nothing left to play. The node already knows its
+        // own moves, so only the boards after it need searching.
+        let mut board = tree[node_idx].board.clone();
+        let mut words = tree[node_idx].moves.clone();
+        let mut playout = vec![];
+        while !words.is_empty() {
+            let found_word = words.swap_remove(rng.below(words.len()));
+            board = board.evolve_via(found_word.clone());
+            playout.push(found_word);
+            words = board.find_words(dict, args.max_children);
+        }
+
+        let score = board.get_score();
+        if score > best_score {
+            best_score = score;
+            best_line = line_to(&tree, node_idx);
+            best_line.extend(playout);
+        }
+
+        // Backpropagation
+        let mut idx = Some(node_idx);
+        while let Some(i) = idx {
+            tree[i].visits += 1;
+            tree[i].total_score += score as f64;
+            idx = tree[i].parent;
+        }
+
+        bar.inc(1);
+    }
+    bar.finish();
+
+    if !args.quiet {
+        println!(
+            "Played {} games, building a tree of {} boards",
+            HumanCount(args.mcts_iterations as u64),
+            HumanCount(tree.len() as u64)
+        );
+    }
+
+    GameResult {
+        score: best_score,
+        moves: best_line,
+        elapsed: game_start_time.elapsed(),
+        estimated_memory: wants_memory_estimate(args).then(|| {
+            tree.iter()
+                .map(|node| {
+                    node.board.deep_size_of()
+                        + node.moves.deep_size_of()
+                        + node.untried.deep_size_of()
+                })
+                .sum()
+        }),
+    }
+}
+
+/// Pick the child of `node_idx` with the best UCT value. Scores are scaled by the best we've seen
+/// so the exploration term means the same thing no matter how big scores get.
+fn select_child(tree: &[Node], node_idx: usize, best_score: u32, exploration: f64) -> usize {
+    let node = &tree[node_idx];
+    let scale = std::cmp::max(best_score, 1) as f64;
+    let ln_visits = (node.visits as f64).ln();
+
+    let uct = |child: &Node| {
+        let exploit = child.total_score / child.visits as f64 / scale;
+        let explore = exploration * (ln_visits / child.visits as f64).sqrt();
+        exploit + explore
+    };
+
+    // Ties go to the first child, keeping things reproducible
+    let mut best_idx = node.children[0];
+    for child_idx in node.children.iter().skip(1) {
+        if uct(&tree[*child_idx]) > uct(&tree[best_idx]) {
+            best_idx = *child_idx;
+        }
+    }
+    best_idx
+}
+
+/// The moves it took to get from the root of the tree down to `node_idx`
+fn line_to(tree: &[Node], node_idx: usize) -> Vec<FoundWord> {
+    let mut line = vec![];
+    let mut idx = node_idx;
+    while let Some(parent) = tree[idx].parent {
+        line.push(tree[idx].board.evolved_via());
+        idx = parent;
+    }
+    line.reverse();
+    line
+}
+
+#[cfg(test)]
+mod mcts_tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    /// The same seed has to give the same numbers, and they need to stay in range
+    fn rng_is_reproducible() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..1_000 {
+            let n = a.below(7);
+            assert_eq!(n, b.below(7));
+            assert!(n < 7);
+        }
+    }
+
+    #[test]
+    /// Seed 0 doesn't get stuck, it just plays the same as seed 1
+    fn rng_survives_zero_seed() {
+        let mut zero = Rng::new(0);
+        let mut one = Rng::new(1);
+        let first = zero.next_u64();
+        assert_ne!(first, 0);
+        assert_eq!(first, one.next_u64());
+        assert_ne!(zero.next_u64(), first);
+    }
+
+    #[test]
+    /// A fixed seed plays the same legal line every time, and on a board this small it finds the
+    /// best one: "cat" then "cats" over the multiplier for 85 (see `exact_tests`)
+    fn search_is_reproducible_and_legal() {
+        let args = Args::parse_from(
+            "spell-tower-solver --strategy mcts --mcts-iterations 200 --seed 7 -q"
+                .split_whitespace(),
+        );
+        let dict = Dictionary::from_words(["at", "act", "cat", "cats", "sat"], 3);
+        let mut tiles = [[Tile::from(Board::EMPTY); Board::WIDTH]; Board::HEIGHT];
+        for (col, c) in "cat".chars().enumerate() {
+            tiles[Board::HEIGHT - 2][col] = c.into();
+        }
+        for (col, c) in "cats".chars().enumerate() {
+            tiles[Board::HEIGHT - 1][col] = c.into();
+        }
+        let mults = vec![(Board::HEIGHT - 1, 1)];
+
+        let result = play_game(&args, &dict, tiles, mults.clone());
+        let again = play_game(&args, &dict, tiles, mults.clone());
+        assert_eq!(result.moves, again.moves);
+        assert_eq!(result.score, 85);
+
+        let mut board = Board::new_from(tiles, mults, 3);
+        for found_word in &result.moves {
+            assert!(board.find_words(&dict, usize::MAX).contains(found_word));
+            board = board.evolve_via(found_word.clone());
+        }
+        assert_eq!(board.get_score(), result.score);
+        assert!(board.find_words(&dict, usize::MAX).is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use deepsize::DeepSizeOf;
+use std::ops::Deref;
 use std::{fmt, hash::Hash};
 
 #[derive(Clone, Eq, Hash, PartialEq, DeepSizeOf, PartialOrd, Ord)]
@@ -25,41 +26,24 @@ impl Position {
         Position { row, col }
     }
 
-    pub fn neighbors(&self, width: usize, height: usize) -> Vec<Position> {
+    pub fn neighbors(&self, width: usize, height: usize) -> Neighbors {
         let mut neighbors = self.cardinal_neighbors(width, height);
-        neighbors.reserve(4);
 
-        if let Some(p) = self.north_west(width, height) {
-            neighbors.push(p);
-        }
-        if let Some(p) = self.north_east(width, height) {
-            neighbors.push(p);
-        }
-        if let Some(p) = self.south_west(width, height) {
-            neighbors.push(p);
-        }
-        if let Some(p) = self.south_east(width, height) {
-            neighbors.push(p);
-        }
+        neighbors.push_some(self.north_west(width, height));
+        neighbors.push_some(self.north_east(width, height));
+        neighbors.push_some(self.south_west(width, height));
+        neighbors.push_some(self.south_east(width, height));
 
         neighbors
     }
 
-    pub fn cardinal_neighbors(&self, width: usize, height: usize) -> Vec<Position> {
-        let mut neighbors: Vec<Position> = Vec::with_capacity(4);
+    pub fn cardinal_neighbors(&self, width: usize, height: usize) -> Neighbors {
+        let mut neighbors = Neighbors::default();
 
-        if let Some(p) = self.north(width, height) {
-            neighbors.push(p);
-        }
-        if let Some(p) = self.east(width, height) {
-            neighbors.push(p);
-        }
-        if let Some(p) = self.west(width, height) {
-            neighbors.push(p);
-        }
-        if let Some(p) = self.south(width, height) {
-            neighbors.push(p);
-        }
+        neighbors.push_some(self.north(width, height));
+        neighbors.push_some(self.east(width, height));
+        neighbors.push_some(self.west(width, height));
+        neighbors.push_some(self.south(width, height));
 
         neighbors
     }
@@ -128,6 +112,41 @@ impl Position {
     }
 }
 
+/// Up to 8 neighboring positions. Lives on the stack, and derefs to a slice of the ones that are
+/// actually there.
+#[derive(Clone, Debug)]
+pub struct Neighbors {
+    positions: [Position; 8],
+    len: u8,
+}
+
+impl Default for Neighbors {
+    fn default() -> Self {
+        const UNUSED: Position = Position { row: 0, col: 0 };
+        Neighbors {
+            positions: [UNUSED; 8],
+            len: 0,
+        }
+    }
+}
+
+impl Neighbors {
+    fn push_some(&mut self, pos: Option<Position>) {
+        if let Some(pos) = pos {
+            self.positions[self.len as usize] = pos;
+            self.len += 1;
+        }
+    }
+}
+
+impl Deref for Neighbors {
+    type Target = [Position];
+
+    fn deref(&self) -> &[Position] {
+        &self.positions[..self.len as usize]
+    }
+}
+
 #[cfg(test)]
 mod position_tests {
     use super::*;
@@ -162,7 +181,7 @@ mod position_tests {
                 let mut expected = $expected;
                 expected.sort();
 
-                let mut result = c.cardinal_neighbors(2, 2);
+                let mut result = c.cardinal_neighbors(2, 2).to_vec();
                 result.sort();
                 assert_eq!(result, expected);
             }
@@ -233,7 +252,7 @@ mod position_tests {
                 let mut expected = $expected;
                 expected.sort();
 
-                let mut result = c.neighbors(2, 2);
+                let mut result = c.neighbors(2, 2).to_vec();
                 result.sort();
                 assert_eq!(result, expected);
             }
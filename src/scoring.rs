@@ -0,0 +1,243 @@
+//! How moves score: what every letter is worth, and the rules for what a move blows up and what
+//! it gets multiplied by. The standard English game's values are built in, anything else can come
+//! from a file (see `sample-input/scoring.ron`) so a game update or another edition doesn't need
+//! a recompile.
+//!
+//! Values are per tile rather than per letter, so other alphabets (`ñ`) and tiles with more than
+//! one letter on (`qu`) get a value of their own.
+
+use crate::tile::{Tile, QU};
+use crate::Args;
+
+use deepsize::DeepSizeOf;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+const STANDARD_LETTER_VALUES: [(char, u32); 26] = [
+    ('a', 1),
+    ('b', 4),
+    ('c', 4),
+    ('d', 3),
+    ('e', 1),
+    ('f', 5),
+    ('g', 3),
+    ('h', 5),
+    ('i', 1),
+    ('j', 9),
+    ('k', 6),
+    ('l', 2),
+    ('m', 4),
+    ('n', 2),
+    ('o', 1),
+    ('p', 4),
+    ('q', 12),
+    ('r', 2),
+    ('s', 1),
+    ('t', 2),
+    ('u', 1),
+    ('v', 5),
+    ('w', 5),
+    ('x', 9),
+    ('y', 5),
+    ('z', 11),
+];
+
+/// A Qu tile is worth its q and u put together
+const STANDARD_QU_VALUE: u32 = 12 + 1;
+
+static STANDARD: LazyLock<Scoring> = LazyLock::new(|| ScoringFile::default().try_into().unwrap());
+
+/// Everything `Board` needs to know to score a move. Tiles are single bytes (see `Tile::code()`),
+/// so everything is looked up by byte.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Scoring {
+    /// What every tile is worth. Blocks, gaps and anything unlisted are worth nothing.
+    values: [u32; 256],
+    /// Tiles that clear their whole row when they're on a word's path
+    row_clearing: [bool; 256],
+    /// What each multiplier on a word's path is worth. They add up rather than multiply.
+    pub multiplier: u32,
+    /// Words at least this long blow up everything directly next to them too
+    pub blast_length: usize,
+}
+
+/// A scoring file. Anything left out keeps its standard value; `letter_values` is all or nothing,
+/// so an edition with a different alphabet doesn't inherit ours. Tiles are written the same way
+/// as on an `InputBoard`: `'ñ'` or `"qu"`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+struct ScoringFile {
+    letter_values: BTreeMap<Tile, u32>,
+    multiplier: u32,
+    blast_length: usize,
+    row_clearing: Vec<Tile>,
+}
+
+impl Default for ScoringFile {
+    fn default() -> Self {
+        ScoringFile {
+            letter_values: STANDARD_LETTER_VALUES
+                .into_iter()
+                .map(|(c, value)| (c.into(), value))
+                .chain([(QU.into(), STANDARD_QU_VALUE)])
+                .collect(),
+            multiplier: 2,
+            blast_length: 5,
+            row_clearing: ['j', 'q', 'x', 'z']
+                .map(Tile::from)
+                .into_iter()
+                .chain([QU.into()])
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<ScoringFile> for Scoring {
+    type Error = String;
+
+    fn try_from(file: ScoringFile) -> Result<Self, Self::Error> {
+        let check = |tile: Tile| {
+            if tile.symbol().chars().all(char::is_lowercase) {
+                Ok(tile.code() as usize)
+            } else {
+                Err(format!(
+                    "{:?} isn't something we can put on a tile",
+                    tile.symbol()
+                ))
+            }
+        };
+
+        let mut values = [0; 256];
+        for (tile, value) in file.letter_values {
+            values[check(tile)?] = value;
+        }
+        let mut row_clearing = [false; 256];
+        for tile in file.row_clearing {
+            row_clearing[check(tile)?] = true;
+        }
+        Ok(Scoring {
+            values,
+            row_clearing,
+            multiplier: file.multiplier,
+            blast_length: file.blast_length,
+        })
+    }
+}
+
+impl From<&Scoring> for ScoringFile {
+    fn from(scoring: &Scoring) -> Self {
+        ScoringFile {
+            letter_values: scoring.letter_values().collect(),
+            multiplier: scoring.multiplier,
+            blast_length: scoring.blast_length,
+            row_clearing: (0..=255)
+                .map(Tile::from_code)
+                .filter(|tile| scoring.clears_row(*tile))
+                .collect(),
+        }
+    }
+}
+
+impl Scoring {
+    /// The values the standard English game uses
+    pub fn standard() -> &'static Scoring {
+        &STANDARD
+    }
+
+    /// --scoring if it was given, the standard values otherwise
+    pub fn from_args(args: &Args) -> &'static Scoring {
+        args.scoring.unwrap_or_else(Scoring::standard)
+    }
+
+    pub fn from_ron(ron: &str) -> Result<Scoring, String> {
+        let file: ScoringFile = ron::from_str(ron).map_err(|e| e.to_string())?;
+        file.try_into()
+    }
+
+    pub fn to_ron(&self) -> String {
+        let pretty = ron::ser::PrettyConfig::new().new_line("\n".to_string());
+        ron::ser::to_string_pretty(&ScoringFile::from(self), pretty).unwrap()
+    }
+
+    /// For --scoring. Every board in a game shares it, so it lives for as long as we do.
+    pub fn load(path: &str) -> Result<&'static Scoring, String> {
+        let ron = std::fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read scoring from {}: {}", path, e))?;
+        let scoring = Scoring::from_ron(&ron).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Box::leak(Box::new(scoring)))
+    }
+
+    pub fn tile_value(&self, tile: Tile) -> u32 {
+        self.values[tile.code() as usize]
+    }
+
+    pub fn clears_row(&self, tile: Tile) -> bool {
+        self.row_clearing[tile.code() as usize]
+    }
+
+    /// Every tile that's worth something, in order
+    pub fn letter_values(&self) -> impl Iterator<Item = (Tile, u32)> + '_ {
+        (0..=255)
+            .map(|c| (Tile::from_code(c), self.values[c as usize]))
+            .filter(|(_, value)| *value > 0)
+    }
+
+    /// Change what `tile` is worth
+    pub fn set_tile_value(&mut self, tile: Tile, value: u32) {
+        let symbol = tile.symbol();
+        assert!(
+            symbol.chars().all(char::is_lowercase),
+            "Can't score {:?}",
+            symbol
+        );
+        self.values[tile.code() as usize] = value;
+    }
+}
+
+/// Shared by every board, so it doesn't count towards any one of them
+impl DeepSizeOf for Scoring {
+    fn deep_size_of_children(&self, _context: &mut deepsize::Context) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod scoring_tests {
+    use super::*;
+
+    #[test]
+    /// The sample file spells out the standard values, and they survive a round trip
+    fn sample_file_is_standard() {
+        let sample = Scoring::from_ron(include_str!("../sample-input/scoring.ron")).unwrap();
+        assert_eq!(&sample, Scoring::standard());
+        assert_eq!(Scoring::from_ron(&sample.to_ron()).unwrap(), sample);
+    }
+
+    #[test]
+    /// Anything left out keeps its standard value, apart from letters which go all together
+    fn missing_settings_are_standard() {
+        let scoring = Scoring::from_ron("(blast_length: 6, letter_values: {'a': 3})").unwrap();
+        assert_eq!(scoring.blast_length, 6);
+        assert_eq!(scoring.multiplier, 2);
+        assert!(scoring.clears_row('q'.into()));
+        assert_eq!(scoring.tile_value('a'.into()), 3);
+        assert_eq!(scoring.tile_value('b'.into()), 0);
+    }
+
+    #[test]
+    /// Any letters can score, on their own or a few to a tile, but blocks and gaps can't
+    fn only_letters_can_score() {
+        let scoring =
+            Scoring::from_ron(r#"(letter_values: {'ñ': 8, "qu": 10}, row_clearing: ['ñ'])"#)
+                .unwrap();
+        assert_eq!(scoring.tile_value('ñ'.into()), 8);
+        assert_eq!(scoring.tile_value("qu".into()), 10);
+        assert!(scoring.clears_row('ñ'.into()));
+        assert!(!scoring.clears_row('q'.into()));
+        assert_eq!(Scoring::from_ron(&scoring.to_ron()).unwrap(), scoring);
+
+        assert!(Scoring::from_ron("(letter_values: {'.': 3})").is_err());
+        assert!(Scoring::from_ron("(row_clearing: [' '])").is_err());
+    }
+}
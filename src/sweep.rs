@@ -0,0 +1,217 @@
+use crate::board::Board;
+use crate::dictionary::Dictionary;
+use crate::game::{GameResult, Strategy};
+use crate::tile::Tile;
+use crate::Args;
+
+use clap::ValueEnum;
+use indicatif::HumanBytes;
+
+/// How to lay out the results of a sweep
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SweepFormat {
+    Csv,
+    Markdown,
+}
+
+/// One game to play in a sweep. Settings a strategy doesn't pay any attention to are `None`, so
+/// we don't play the same game over and over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    pub strategy: Strategy,
+    pub max_children: Option<usize>,
+    pub max_gen_size: Option<usize>,
+}
+
+impl Config {
+    fn apply(&self, args: &mut Args) {
+        args.strategy = self.strategy;
+        if let Some(max_children) = self.max_children {
+            args.max_children = max_children;
+        }
+        if let Some(max_gen_size) = self.max_gen_size {
+            args.max_gen_size = max_gen_size;
+        }
+    }
+}
+
+/// Whether we've been asked for a sweep rather than a single game
+pub fn wanted(args: &Args) -> bool {
+    args.start_max_children.is_some()
+        || !args.sweep_children.is_empty()
+        || !args.sweep_gen_sizes.is_empty()
+        || !args.sweep_strategies.is_empty()
+}
+
+/// Every combination of the swept settings. Anything not being swept sticks to what's in `args`,
+/// and -s is shorthand for sweeping -c from there up to the regular -c.
+pub fn configs(args: &Args) -> Vec<Config> {
+    let or_default = |swept: &Vec<usize>, default: usize| {
+        if swept.is_empty() {
+            vec![default]
+        } else {
+            swept.clone()
+        }
+    };
+
+    let children = match args.start_max_children {
+        Some(start) if args.sweep_children.is_empty() => (start..=args.max_children).collect(),
+        _ => or_default(&args.sweep_children, args.max_children),
+    };
+    let gen_sizes = or_default(&args.sweep_gen_sizes, args.max_gen_size);
+    let strategies = if args.sweep_strategies.is_empty() {
+        vec![args.strategy]
+    } else {
+        args.sweep_strategies.clone()
+    };
+
+    let mut configs = vec![];
+    for strategy in strategies {
+        for max_children in &children {
+            for max_gen_size in &gen_sizes {
+                let config = Config {
+                    strategy,
+                    max_children: match strategy {
+                        Strategy::Exact => None,
+                        _ => Some(*max_children),
+                    },
+                    max_gen_size: match strategy {
+                        Strategy::Beam => Some(*max_gen_size),
+                        _ => None,
+                    },
+                };
+                if !configs.contains(&config) {
+                    configs.push(config);
+                }
+            }
+        }
+    }
+    configs
+}
+
+/// Play every game in the sweep and print a table of how they went
+pub fn run(
+    args: &mut Args,
+    dict: &Dictionary,
+    board: [[Tile; Board::WIDTH]; Board::HEIGHT],
+    mult_locs: Vec<(usize, usize)>,
+) {
+    let mut rows = vec![];
+    for config in configs(args) {
+        config.apply(args);
+        let result = args.strategy.play(args, dict, board, mult_locs.clone());
+        rows.push((config, result));
+    }
+    print!("{}", render(&rows, args.sweep_format));
+}
+
+fn render(rows: &[(Config, GameResult)], format: SweepFormat) -> String {
+    let or_dash = |setting: Option<usize>| setting.map_or("-".to_string(), |s| s.to_string());
+
+    let mut out = String::new();
+    match format {
+        SweepFormat::Csv => {
+            out.push_str(
+                "strategy,max_children,max_gen_size,score,moves,seconds,estimated_bytes\n",
+            );
+        }
+        SweepFormat::Markdown => {
+            out.push_str("| strategy | -c | -g | score | moves | time | est. memory |\n");
+            out.push_str("|---|--:|--:|--:|--:|--:|--:|\n");
+        }
+    }
+
+    for (config, result) in rows {
+        let strategy = config.strategy.to_possible_value().unwrap();
+        let cells = [
+            strategy.get_name().to_string(),
+            or_dash(config.max_children),
+            or_dash(config.max_gen_size),
+            result.score.to_string(),
+            result.moves.len().to_string(),
+            format!("{:.2}", result.elapsed.as_secs_f64()),
+            match (format, result.estimated_memory) {
+                (_, None) => "-".to_string(),
+                (SweepFormat::Csv, Some(bytes)) => bytes.to_string(),
+                (SweepFormat::Markdown, Some(bytes)) => HumanBytes(bytes as u64).to_string(),
+            },
+        ];
+        match format {
+            SweepFormat::Csv => out.push_str(&cells.join(",")),
+            SweepFormat::Markdown => {
+                out.push_str("| ");
+                out.push_str(&cells.join(" | "));
+                out.push_str(" |");
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod sweep_tests {
+    use super::*;
+    use clap::Parser;
+    use std::time::Duration;
+
+    fn args_for(cli: &str) -> Args {
+        Args::parse_from(format!("spell-tower-solver {}", cli).split_whitespace())
+    }
+
+    #[test]
+    /// -s is just a sweep of -c
+    fn start_max_children_is_a_sweep() {
+        let args = args_for("-s 3 -c 5 -g 100");
+        assert!(wanted(&args));
+        let children = configs(&args)
+            .iter()
+            .map(|c| c.max_children.unwrap())
+            .collect::<Vec<usize>>();
+        assert_eq!(children, vec![3, 4, 5]);
+    }
+
+    #[test]
+    /// Exact doesn't use -c or -g, and mcts doesn't use -g, so there's no point repeating them
+    fn ignored_settings_collapse() {
+        let args = args_for(
+            "--sweep-children 3,4 --sweep-gen-sizes 10,20 --sweep-strategies beam,mcts,exact",
+        );
+        let configs = configs(&args);
+        assert_eq!(configs.len(), 4 + 2 + 1);
+        assert_eq!(
+            configs.last(),
+            Some(&Config {
+                strategy: Strategy::Exact,
+                max_children: None,
+                max_gen_size: None
+            })
+        );
+    }
+
+    #[test]
+    fn no_sweep_by_default() {
+        assert!(!wanted(&args_for("-c 4")));
+    }
+
+    #[test]
+    fn renders_csv() {
+        let rows = vec![(
+            Config {
+                strategy: Strategy::Mcts,
+                max_children: Some(4),
+                max_gen_size: None,
+            },
+            GameResult {
+                score: 1234,
+                moves: vec![],
+                elapsed: Duration::from_millis(1500),
+                estimated_memory: Some(2048),
+            },
+        )];
+        assert_eq!(
+            render(&rows, SweepFormat::Csv),
+            "strategy,max_children,max_gen_size,score,moves,seconds,estimated_bytes\nmcts,4,-,1234,0,1.50,2048\n"
+        );
+    }
+}
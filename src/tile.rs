@@ -0,0 +1,248 @@
+//! What's printed on a tile. Usually that's one letter, but it can be any letter (`é`, `ñ`) or a
+//! few of them at once (`qu`), so every tile is a symbol: the string it adds to a word.
+//!
+//! Boards store tiles as one byte each. ASCII (every English letter, `Board::BLOCK` and
+//! `Board::EMPTY`) is stored as itself, so an English board looks exactly like it always has.
+//! Any other symbol gets a code from 128 up the first time it's seen, shared by everything for as
+//! long as we're running.
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+use deepsize::DeepSizeOf;
+
+/// Every ASCII byte, so each has a `&'static str` to hand out as its symbol
+static ASCII: [u8; 128] = {
+    let mut ascii = [0; 128];
+    let mut c = 0;
+    while c < 128 {
+        ascii[c] = c as u8;
+        c += 1;
+    }
+    ascii
+};
+
+/// Symbols that aren't a single ASCII character. Tile `128 + i` is `SYMBOLS[i]`, and they're only
+/// ever added to so codes never change. That means a symbol can be read without taking a lock,
+/// which matters since hashing and the word search look them up from every thread at once.
+static SYMBOLS: [OnceLock<&'static str>; 128] = [const { OnceLock::new() }; 128];
+
+/// Held while a new symbol gets its code, so two threads can't both add the same one
+static ADDING: Mutex<()> = Mutex::new(());
+
+/// The one multi-letter tile the English game has, see `Tile::as_qu()`
+pub const QU: &str = "qu";
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, DeepSizeOf)]
+pub struct Tile(u8);
+
+impl Tile {
+    /// The tile for `symbol`, giving it a code if it hasn't got one yet
+    pub fn new(symbol: &str) -> Result<Tile, String> {
+        if let Some(tile) = Tile::find(symbol) {
+            return Ok(tile);
+        }
+        if symbol.is_empty() {
+            return Err("A tile needs something on it".to_string());
+        }
+
+        let _adding = ADDING.lock().unwrap();
+        // Someone else might have added it since we looked
+        if let Some(tile) = Tile::find(symbol) {
+            return Ok(tile);
+        }
+        let Some(i) = SYMBOLS.iter().position(|s| s.get().is_none()) else {
+            return Err(format!(
+                "No room left for {:?}, that's 256 different tiles",
+                symbol
+            ));
+        };
+        SYMBOLS[i]
+            .set(Box::leak(symbol.to_string().into_boxed_str()))
+            .unwrap();
+        Ok(Tile(128 + i as u8))
+    }
+
+    /// The tile for `symbol` if it's got one, without adding it
+    pub fn find(symbol: &str) -> Option<Tile> {
+        match symbol.as_bytes() {
+            [c] if c.is_ascii() => Some(Tile(*c)),
+            _ => SYMBOLS
+                .iter()
+                .map_while(OnceLock::get)
+                .position(|s| *s == symbol)
+                .map(|i| Tile(128 + i as u8)),
+        }
+    }
+
+    /// The tile a board stored as `code`
+    pub fn from_code(code: u8) -> Tile {
+        Tile(code)
+    }
+
+    /// How the tile is stored on a board
+    pub fn code(self) -> u8 {
+        self.0
+    }
+
+    /// What the tile adds to a word
+    pub fn symbol(self) -> &'static str {
+        match self.0 {
+            c @ 0..128 => std::str::from_utf8(&ASCII[c as usize..=c as usize]).unwrap(),
+            c => SYMBOLS[c as usize - 128]
+                .get()
+                .expect("Tile codes from 128 up only come from Tile::new()"),
+        }
+    }
+
+    /// How many letters the tile adds to a word
+    pub fn letters(self) -> usize {
+        match self.0 {
+            0..128 => 1,
+            _ => self.symbol().chars().count(),
+        }
+    }
+
+    /// The tile as it is in Qu mode (see --qu), where the game shows Qu instead of Q. A q becomes
+    /// a single tile spelling qu, everything else stays as it is.
+    pub fn as_qu(self) -> Tile {
+        if self == 'q' {
+            Tile::from(QU)
+        } else {
+            self
+        }
+    }
+
+    /// Add the tile's symbol to the end of `word`. This is on the word search's hot path, so plain
+    /// ASCII doesn't even look at `SYMBOLS`.
+    pub fn push_onto(self, word: &mut String) {
+        match self.0 {
+            c @ 0..128 => word.push(c as char),
+            _ => word.push_str(self.symbol()),
+        }
+    }
+}
+
+impl From<char> for Tile {
+    fn from(c: char) -> Self {
+        Tile::new(c.encode_utf8(&mut [0; 4])).unwrap()
+    }
+}
+
+impl From<&str> for Tile {
+    fn from(symbol: &str) -> Self {
+        Tile::new(symbol).unwrap()
+    }
+}
+
+/// Whether the tile is just `c`, so it can be checked against `Board::BLOCK` and friends
+impl PartialEq<char> for Tile {
+    fn eq(&self, c: &char) -> bool {
+        if c.is_ascii() {
+            return self.0 == *c as u8;
+        }
+        let mut chars = self.symbol().chars();
+        chars.next() == Some(*c) && chars.next().is_none()
+    }
+}
+
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+/// Single characters are written as a `char`, anything longer as a string
+impl Serialize for Tile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let symbol = self.symbol();
+        let mut chars = symbol.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => serializer.serialize_char(c),
+            _ => serializer.serialize_str(symbol),
+        }
+    }
+}
+
+/// Either a `char` (`'ñ'`) or a string (`"qu"`)
+impl<'de> Deserialize<'de> for Tile {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TileVisitor;
+
+        impl Visitor<'_> for TileVisitor {
+            type Value = Tile;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a tile, as a char or a string")
+            }
+
+            fn visit_char<E: de::Error>(self, c: char) -> Result<Tile, E> {
+                Tile::new(c.encode_utf8(&mut [0; 4])).map_err(E::custom)
+            }
+
+            fn visit_str<E: de::Error>(self, symbol: &str) -> Result<Tile, E> {
+                Tile::new(symbol).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(TileVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tile_tests {
+    use super::*;
+
+    #[test]
+    /// ASCII is stored as itself, anything else gets the same code every time it's asked for
+    fn symbols_keep_their_codes() {
+        assert_eq!(Tile::from('a').code(), b'a');
+        assert_eq!(Tile::from('.').symbol(), ".");
+
+        let enye = Tile::from('ñ');
+        assert!(enye.code() >= 128);
+        assert_eq!(Tile::from("ñ"), enye);
+        assert_eq!(Tile::find("ñ"), Some(enye));
+        assert_eq!(enye.symbol(), "ñ");
+        assert_eq!(enye.letters(), 1);
+
+        let qu = Tile::from("qu");
+        assert_ne!(qu, enye);
+        assert_eq!(qu.letters(), 2);
+        assert_eq!(qu.to_string(), "qu");
+
+        let mut word = String::from("s");
+        qu.push_onto(&mut word);
+        Tile::from('a').push_onto(&mut word);
+        assert_eq!(word, "squa");
+
+        assert!(Tile::new("").is_err());
+        assert_eq!(Tile::find("not a tile"), None);
+    }
+
+    #[test]
+    /// Threads racing to add the same symbol all end up with the one code, and can read it back
+    fn racing_threads_share_a_code() {
+        let tiles = std::thread::scope(|scope| {
+            let racers = (0..8)
+                .map(|_| scope.spawn(|| Tile::from("ß")))
+                .collect::<Vec<_>>();
+            racers
+                .into_iter()
+                .map(|racer| racer.join().unwrap())
+                .collect::<Vec<Tile>>()
+        });
+        assert!(tiles.iter().all(|t| *t == tiles[0] && t.symbol() == "ß"));
+    }
+
+    #[test]
+    /// Tiles in RON can be a char or a string, and come back out the same way
+    fn tiles_in_ron() {
+        let tiles: Vec<Tile> = ron::from_str(r#"['a', 'é', "qu", "b"]"#).unwrap();
+        let symbols = tiles.iter().map(|t| t.symbol()).collect::<Vec<_>>();
+        assert_eq!(symbols, ["a", "é", "qu", "b"]);
+        assert_eq!(ron::to_string(&tiles).unwrap(), r#"['a','é',"qu",'b']"#);
+    }
+}